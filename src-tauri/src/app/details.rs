@@ -0,0 +1,382 @@
+use super::{App, InputEvent, View, keep_selection};
+use crate::{AssetType, Game, System};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// how much of the description one scroll step moves past, roughly two lines
+const SCROLL_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetailAction {
+	Launch,
+	ToggleFavorite,
+	/// Cycled with Left and Right, or Ok, through the system's alternative emulators.
+	ChooseEmulator,
+	/// Opens the metadata editor.
+	Edit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetailEntry {
+	pub action: DetailAction,
+	pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetailField {
+	pub label: String,
+	pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Media {
+	pub kind: AssetType,
+	pub path: PathBuf,
+}
+
+/// Everything known about the selected game, for [View::Details].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameDetails {
+	pub system: String,
+	pub name: String,
+	pub description: Option<String>,
+	/// The metadata that is set, in display form.
+	pub fields: Vec<DetailField>,
+	/// Every media file the gamelist names, as absolute paths.
+	pub media: Vec<Media>,
+	pub actions: Vec<DetailEntry>,
+	pub selected: usize,
+	/// How far the description is scrolled, in steps.
+	pub scroll: usize,
+}
+
+impl GameDetails {
+	/// The details of `game` from `system`, the system it comes from. `restricted` leaves out the
+	/// actions that change the game.
+	pub fn new(system: &System, game: &Game, restricted: bool) -> Self {
+		let mut fields = Vec::new();
+		let mut field = |label: &str, value: Option<String>| {
+			if let Some(value) = value.filter(|x| !x.trim().is_empty()) {
+				fields.push(DetailField {
+					label: label.to_string(),
+					value,
+				});
+			}
+		};
+		let flag = |value: Option<bool>| value.unwrap_or_default().then(|| "Yes".to_string());
+
+		field("Developer", game.developer.clone());
+		field("Publisher", game.publisher.clone());
+		field("Released", game.releasedate.as_deref().map(es_date));
+		field("Genre", game.genre.clone());
+		field("Players", game.players.clone());
+		field(
+			"Rating",
+			game.rating
+				.as_deref()
+				.and_then(|x| x.trim().parse::<f64>().ok())
+				.map(|x| format!("{:.1} / 5", x * 5.0)),
+		);
+		field("Times Played", game.playcount.map(|x| x.to_string()));
+		field("Last Played", game.lastplayed.as_deref().map(es_date));
+		field("Favorite", flag(game.favorite));
+		field("Completed", flag(game.completed));
+		field("Kid Game", flag(game.kidgame));
+		field("Broken", flag(game.broken));
+		field(
+			"File",
+			game.path.as_ref().map(|x| x.to_string_lossy().to_string()),
+		);
+
+		let emulators = system.emulators();
+		let emulator = game
+			.altemulator
+			.clone()
+			.or_else(|| emulators.first().cloned());
+		if !emulators.is_empty() {
+			field("Emulator", emulator.clone());
+		}
+
+		let media = [
+			(AssetType::Image, &game.image),
+			(AssetType::Thumbnail, &game.thumbnail),
+			(AssetType::Marquee, &game.marquee),
+			(AssetType::Video, &game.video),
+		]
+		.into_iter()
+		.filter_map(|(kind, path)| {
+			let path = path.as_ref().filter(|x| !x.as_os_str().is_empty())?;
+			Some(Media {
+				kind,
+				path: system.resolve(path)?,
+			})
+		})
+		.collect();
+
+		let mut actions = vec![DetailEntry {
+			action: DetailAction::Launch,
+			label: "Launch".to_string(),
+		}];
+
+		if !restricted {
+			actions.push(DetailEntry {
+				action: DetailAction::ToggleFavorite,
+				label: match game.favorite.unwrap_or_default() {
+					true => "Remove from Favorites".to_string(),
+					false => "Add to Favorites".to_string(),
+				},
+			});
+
+			if let Some(emulator) = emulator.filter(|_| !emulators.is_empty()) {
+				actions.push(DetailEntry {
+					action: DetailAction::ChooseEmulator,
+					label: format!("Emulator: {}", emulator),
+				});
+			}
+
+			actions.push(DetailEntry {
+				action: DetailAction::Edit,
+				label: "Edit Metadata".to_string(),
+			});
+		}
+
+		Self {
+			system: system.fullname.clone(),
+			name: game.name.clone().unwrap_or_default(),
+			description: game.desc.clone(),
+			fields,
+			media,
+			actions,
+			selected: 0,
+			scroll: 0,
+		}
+	}
+
+	/// How far the description can be scrolled.
+	pub fn max_scroll(&self) -> usize {
+		self.description
+			.as_deref()
+			.map_or(0, |x| x.chars().count() / SCROLL_CHARS)
+	}
+}
+
+// `YYYYMMDDTHHMMSS` as `YYYY-MM-DD`, or as it is when it isn't in that form
+fn es_date(value: &str) -> String {
+	match (value.get(..4), value.get(4..6), value.get(6..8)) {
+		(Some(year), Some(month), Some(day)) if value[..8].chars().all(|x| x.is_ascii_digit()) => {
+			format!("{}-{}-{}", year, month, day)
+		}
+		_ => value.to_string(),
+	}
+}
+
+impl App {
+	/// Opens the details of the selected game over the game list.
+	pub(crate) async fn open_details(&self) {
+		let mut orientation = self.orientation.lock().await;
+		if self
+			.all_systems
+			.lock()
+			.await
+			.gamelist_len(orientation.system_index)
+			> 0
+		{
+			orientation.views.push(View::Details {
+				selected: 0,
+				scroll: 0,
+			});
+		}
+	}
+
+	/// The details of the selected game, while [View::Details] is shown.
+	pub async fn game_details(&self) -> Option<GameDetails> {
+		let restricted = self.config.lock().await.ui_mode.is_restricted();
+		let orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+
+		let Some(View::Details { selected, scroll }) = orientation.views.last() else {
+			return None;
+		};
+
+		let game = systems
+			.system
+			.get(orientation.system_index)?
+			.gamelist
+			.get(orientation.gamelist_index)?;
+		let system = systems.origin(orientation.system_index, game)?;
+
+		let mut details = GameDetails::new(system, game, restricted);
+		details.selected = (*selected).min(details.actions.len() - 1);
+		details.scroll = (*scroll).min(details.max_scroll());
+		Some(details)
+	}
+
+	/// Input while the details are shown. Up and Down pick an action, Page Up and Page Down
+	/// scroll the description, and Cancel goes back to the game list.
+	pub(crate) async fn event_details(&self, event: InputEvent) {
+		let Some(details) = self.game_details().await else {
+			// the game went away
+			self.orientation.lock().await.views.pop();
+			return;
+		};
+
+		let len = details.actions.len();
+		let action = details.actions[details.selected].action;
+		let mut selected = details.selected;
+		let mut scroll = details.scroll;
+
+		match event {
+			InputEvent::Cancel | InputEvent::Details => {
+				self.orientation.lock().await.views.pop();
+				return;
+			}
+			InputEvent::Menu => return self.event_input_menu().await,
+			InputEvent::Up => selected = (selected + len - 1) % len,
+			InputEvent::Down => selected = (selected + 1) % len,
+			InputEvent::PageUp => scroll = scroll.saturating_sub(1),
+			InputEvent::PageDown => scroll = (scroll + 1).min(details.max_scroll()),
+			InputEvent::Left | InputEvent::Right if action == DetailAction::ChooseEmulator => {
+				return self.cycle_emulator(event == InputEvent::Right).await;
+			}
+			InputEvent::Ok => {
+				return match action {
+					DetailAction::Launch => {
+						self.event_input_ok().await;
+						self.save_navigation().await;
+					}
+					DetailAction::ToggleFavorite => self.event_input_favorite().await,
+					DetailAction::ChooseEmulator => self.cycle_emulator(true).await,
+					DetailAction::Edit => self.open_editor().await,
+				};
+			}
+			_ => return,
+		}
+
+		if let Some(View::Details {
+			selected: s,
+			scroll: x,
+		}) = self.orientation.lock().await.views.last_mut()
+		{
+			*s = selected;
+			*x = scroll;
+		}
+	}
+
+	/// Switches the selected game to the next or previous of its system's emulators, and saves
+	/// the choice in its gamelist. The default emulator isn't written out.
+	async fn cycle_emulator(&self, forward: bool) {
+		let config = self.config.lock().await;
+		if config.ui_mode.is_restricted() {
+			return;
+		}
+
+		let mut orientation = self.orientation.lock().await;
+		let mut systems = self.all_systems.lock().await;
+
+		let Some(game) = systems
+			.system
+			.get(orientation.system_index)
+			.and_then(|x| x.gamelist.get(orientation.gamelist_index))
+		else {
+			return;
+		};
+
+		let Some((origin, path)) = systems
+			.origin(orientation.system_index, game)
+			.zip(game.path.clone())
+		else {
+			return;
+		};
+
+		let emulators = origin.emulators();
+		let len = emulators.len();
+		if len == 0 {
+			return;
+		}
+
+		let current = game
+			.altemulator
+			.as_ref()
+			.and_then(|x| emulators.iter().position(|label| label == x))
+			.unwrap_or_default();
+		let next = if forward {
+			(current + 1) % len
+		} else {
+			(current + len - 1) % len
+		};
+		let emulator = (next > 0).then(|| emulators[next].clone());
+		let origin = origin.name.clone();
+
+		keep_selection(&mut orientation, &mut systems, |systems| {
+			if let Some(system) =
+				systems.update_game(&origin, &path, |x| x.altemulator = emulator.clone())
+			{
+				self.save_gamelist(system);
+			}
+
+			systems.refresh_collections(&config);
+			systems.apply_config(&config);
+		});
+
+		self.systems_changed();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_game_details() {
+		let system: System = quick_xml::de::from_str(
+			r#"<system>
+				<name>snes</name>
+				<fullname>Super Nintendo</fullname>
+				<path>/roms/snes</path>
+				<extension>.sfc</extension>
+				<command label="Snes9x">snes9x %ROM%</command>
+				<command label="bsnes">bsnes %ROM%</command>
+				<platform>snes</platform>
+			</system>"#,
+		)
+		.unwrap();
+
+		let game = Game {
+			name: Some("Axelay".into()),
+			path: Some("./axelay.sfc".into()),
+			image: Some("./images/axelay.png".into()),
+			video: Some("".into()),
+			releasedate: Some("19920911T000000".into()),
+			rating: Some("0.9".into()),
+			altemulator: Some("bsnes".into()),
+			desc: Some("x".repeat(SCROLL_CHARS * 2)),
+			..Default::default()
+		};
+
+		let details = GameDetails::new(&system, &game, false);
+		let value = |label: &str| {
+			details
+				.fields
+				.iter()
+				.find(|x| x.label == label)
+				.map(|x| x.value.as_str())
+		};
+		assert_eq!(value("Released"), Some("1992-09-11"));
+		assert_eq!(value("Rating"), Some("4.5 / 5"));
+		assert_eq!(value("Emulator"), Some("bsnes"));
+		assert_eq!(value("Developer"), None);
+		assert_eq!(
+			details.media,
+			vec![Media {
+				kind: AssetType::Image,
+				path: "/roms/snes/images/axelay.png".into(),
+			}]
+		);
+		assert_eq!(details.actions.len(), 4);
+		assert_eq!(details.max_scroll(), 2);
+
+		let restricted = GameDetails::new(&system, &game, true);
+		assert_eq!(restricted.actions.len(), 1);
+	}
+}
@@ -0,0 +1,385 @@
+use super::{App, InputEvent, KeyboardPurpose, OnScreenKeyboard, View, keep_selection};
+use crate::Game;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// The parts of a game that can be changed from the frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataField {
+	Name,
+	SortName,
+	Genre,
+	Players,
+	/// Changed by half a star with Left and Right.
+	Rating,
+	Favorite,
+	Completed,
+	KidGame,
+	Broken,
+	Hidden,
+}
+
+impl MetadataField {
+	pub fn all() -> Vec<Self> {
+		vec![
+			Self::Name,
+			Self::SortName,
+			Self::Genre,
+			Self::Players,
+			Self::Rating,
+			Self::Favorite,
+			Self::Completed,
+			Self::KidGame,
+			Self::Broken,
+			Self::Hidden,
+		]
+	}
+
+	/// Whether the field is typed on the keyboard rather than stepped through.
+	pub fn is_text(&self) -> bool {
+		matches!(
+			self,
+			Self::Name | Self::SortName | Self::Genre | Self::Players
+		)
+	}
+
+	/// The field as typed, for text fields.
+	pub fn text(&self, game: &Game) -> String {
+		match self {
+			Self::Name => game.name.clone(),
+			Self::SortName => game.sortname.clone(),
+			Self::Genre => game.genre.clone(),
+			Self::Players => game.players.clone(),
+			_ => None,
+		}
+		.unwrap_or_default()
+	}
+
+	/// The field as shown in the editor.
+	pub fn value(&self, game: &Game) -> String {
+		let flag = |x: Option<bool>| if x.unwrap_or_default() { "Yes" } else { "No" }.to_string();
+
+		match self {
+			Self::Rating => match stars(game) {
+				x if x > 0.0 => format!("{:.1} / 5", x),
+				_ => "None".to_string(),
+			},
+			Self::Favorite => flag(game.favorite),
+			Self::Completed => flag(game.completed),
+			Self::KidGame => flag(game.kidgame),
+			Self::Broken => flag(game.broken),
+			Self::Hidden => flag(game.hidden),
+			_ => self.text(game),
+		}
+	}
+
+	/// Sets a text field from what was typed. Empty text clears the field, except for the name,
+	/// which every game needs.
+	pub fn set_text(&self, game: &mut Game, text: &str) -> Result<()> {
+		let text = text.trim();
+		let value = (!text.is_empty()).then(|| text.to_string());
+
+		match self {
+			Self::Name => {
+				if value.is_none() {
+					return Err(anyhow!("The name can't be empty"));
+				}
+				game.name = value;
+			}
+			Self::SortName => game.sortname = value,
+			Self::Genre => game.genre = value,
+			Self::Players => {
+				if value.as_deref().is_some_and(|x| !valid_players(x)) {
+					return Err(anyhow!(
+						"Players must be a number like 2, or a range like 1-4"
+					));
+				}
+				game.players = value;
+			}
+			_ => return Err(anyhow!("{} isn't typed in", self)),
+		}
+
+		Ok(())
+	}
+
+	/// Steps the rating up or down by half a star, or flips a flag.
+	pub fn step(&self, game: &mut Game, forward: bool) {
+		// ES-DE leaves flags out rather than writing false
+		let flip = |x: &mut Option<bool>| *x = (!x.unwrap_or_default()).then_some(true);
+
+		match self {
+			Self::Rating => {
+				let stars = (stars(game) + if forward { 0.5 } else { -0.5 }).clamp(0.0, 5.0);
+				game.rating = (stars > 0.0).then(|| format!("{}", stars / 5.0));
+			}
+			Self::Favorite => flip(&mut game.favorite),
+			Self::Completed => flip(&mut game.completed),
+			Self::KidGame => flip(&mut game.kidgame),
+			Self::Broken => flip(&mut game.broken),
+			Self::Hidden => flip(&mut game.hidden),
+			_ => {}
+		}
+	}
+
+	/// Sets the field of `to` to what it is in `from`.
+	fn copy(&self, from: &Game, to: &mut Game) {
+		match self {
+			Self::Name => to.name = from.name.clone(),
+			Self::SortName => to.sortname = from.sortname.clone(),
+			Self::Genre => to.genre = from.genre.clone(),
+			Self::Players => to.players = from.players.clone(),
+			Self::Rating => to.rating = from.rating.clone(),
+			Self::Favorite => to.favorite = from.favorite,
+			Self::Completed => to.completed = from.completed,
+			Self::KidGame => to.kidgame = from.kidgame,
+			Self::Broken => to.broken = from.broken,
+			Self::Hidden => to.hidden = from.hidden,
+		}
+	}
+}
+
+impl std::fmt::Display for MetadataField {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::Name => "Name",
+			Self::SortName => "Sort Name",
+			Self::Genre => "Genre",
+			Self::Players => "Players",
+			Self::Rating => "Rating",
+			Self::Favorite => "Favorite",
+			Self::Completed => "Completed",
+			Self::KidGame => "Kid Game",
+			Self::Broken => "Broken",
+			Self::Hidden => "Hidden",
+		})
+	}
+}
+
+// the rating is stored from 0 to 1; rounded to half stars
+fn stars(game: &Game) -> f64 {
+	let rating: f64 = game
+		.rating
+		.as_deref()
+		.and_then(|x| x.trim().parse().ok())
+		.unwrap_or_default();
+	(rating.clamp(0.0, 1.0) * 10.0).round() / 2.0
+}
+
+// "2" or "1-4"
+fn valid_players(value: &str) -> bool {
+	let numbers: Vec<Option<u32>> = value.split('-').map(|x| x.parse().ok()).collect();
+
+	match numbers[..] {
+		[Some(x)] => x > 0,
+		[Some(from), Some(to)] => from > 0 && from <= to,
+		_ => false,
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorEntry {
+	pub field: MetadataField,
+	pub label: String,
+	pub value: String,
+}
+
+/// The metadata of the selected game, as shown while [View::Editor] is open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataEditor {
+	pub name: String,
+	pub entries: Vec<EditorEntry>,
+	pub selected: usize,
+}
+
+impl App {
+	/// Opens the metadata editor for the selected game.
+	pub(crate) async fn open_editor(&self) {
+		let mut orientation = self.orientation.lock().await;
+		if self
+			.all_systems
+			.lock()
+			.await
+			.gamelist_len(orientation.system_index)
+			> 0
+		{
+			orientation.views.push(View::Editor { selected: 0 });
+		}
+	}
+
+	pub async fn metadata_editor(&self) -> Option<MetadataEditor> {
+		let orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+
+		let Some(View::Editor { selected }) = orientation.views.last() else {
+			return None;
+		};
+
+		let game = systems
+			.system
+			.get(orientation.system_index)?
+			.gamelist
+			.get(orientation.gamelist_index)?;
+
+		let entries: Vec<EditorEntry> = MetadataField::all()
+			.into_iter()
+			.map(|field| EditorEntry {
+				field,
+				label: field.to_string(),
+				value: field.value(game),
+			})
+			.collect();
+
+		Some(MetadataEditor {
+			name: game.name.clone().unwrap_or_default(),
+			selected: (*selected).min(entries.len() - 1),
+			entries,
+		})
+	}
+
+	/// Input while the editor is open. Ok types a text field on the keyboard; Ok, Left and Right
+	/// change the others straight away.
+	pub(crate) async fn event_editor(&self, event: InputEvent) {
+		if self.config.lock().await.ui_mode.is_restricted() {
+			self.orientation.lock().await.close_editor();
+			return;
+		}
+
+		let Some(editor) = self.metadata_editor().await else {
+			self.orientation.lock().await.views.pop();
+			return;
+		};
+
+		let len = editor.entries.len();
+		let field = editor.entries[editor.selected].field;
+		let selected = match event {
+			InputEvent::Up => (editor.selected + len - 1) % len,
+			InputEvent::Down => (editor.selected + 1) % len,
+			InputEvent::Cancel => {
+				self.orientation.lock().await.views.pop();
+				return;
+			}
+			InputEvent::Ok if field.is_text() => {
+				let mut keyboard = OnScreenKeyboard::new(KeyboardPurpose::EditMetadata);
+				keyboard.text = editor.entries[editor.selected].value.clone();
+				self.orientation.lock().await.keyboard = Some(keyboard);
+				return;
+			}
+			InputEvent::Ok | InputEvent::Left | InputEvent::Right if !field.is_text() => {
+				let forward = event != InputEvent::Left;
+				self.save_field(field, |game| {
+					field.step(game, forward);
+					Ok(())
+				})
+				.await;
+				return;
+			}
+			_ => return,
+		};
+
+		if let Some(View::Editor { selected: x }) = self.orientation.lock().await.views.last_mut() {
+			*x = selected;
+		}
+	}
+
+	/// Sets the text field selected in the editor to what was typed on the keyboard.
+	pub(crate) async fn edit_text(&self, text: &str) {
+		let Some(editor) = self.metadata_editor().await else {
+			return;
+		};
+
+		let field = editor.entries[editor.selected].field;
+		self.save_field(field, |game| field.set_text(game, text))
+			.await;
+	}
+
+	/// Changes one field of the selected game with `f` and writes it to its gamelist. Nothing is
+	/// changed when `f` turns the edit down; the player is told why instead.
+	async fn save_field(&self, field: MetadataField, f: impl FnOnce(&mut Game) -> Result<()>) {
+		let config = self.config.lock().await;
+		let mut orientation = self.orientation.lock().await;
+		if config.ui_mode.is_restricted() {
+			orientation.close_editor();
+			return;
+		}
+
+		let mut systems = self.all_systems.lock().await;
+
+		let Some(game) = systems
+			.system
+			.get(orientation.system_index)
+			.and_then(|x| x.gamelist.get(orientation.gamelist_index))
+		else {
+			return;
+		};
+
+		let Some((origin, path)) = systems
+			.origin(orientation.system_index, game)
+			.map(|x| x.name.clone())
+			.zip(game.path.clone())
+		else {
+			self.toast("This game has no path in gamelist.xml");
+			return;
+		};
+
+		let mut edited = game.clone();
+		if let Err(e) = f(&mut edited) {
+			self.toast(e.to_string());
+			return;
+		}
+
+		keep_selection(&mut orientation, &mut systems, |systems| {
+			if let Some(system) = systems.update_game(&origin, &path, |x| field.copy(&edited, x)) {
+				self.save_gamelist(system);
+			}
+
+			systems.refresh_collections(&config);
+			systems.apply_config(&config);
+		});
+
+		// a hidden game is gone from the list, and the selection with it
+		if edited.is_hidden() {
+			orientation
+				.views
+				.retain(|x| !matches!(x, View::Editor { .. } | View::Details { .. }));
+			self.toast(format!("{} is hidden now", edited.name.unwrap_or_default()));
+		}
+
+		self.systems_changed();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_metadata_edits() {
+		let mut game = Game {
+			name: Some("Axelay".into()),
+			rating: Some("0.7".into()),
+			..Default::default()
+		};
+
+		assert!(MetadataField::Name.set_text(&mut game, "  ").is_err());
+		assert_eq!(game.name.as_deref(), Some("Axelay"));
+		MetadataField::SortName
+			.set_text(&mut game, " axelay ")
+			.unwrap();
+		assert_eq!(game.sortname.as_deref(), Some("axelay"));
+
+		assert!(MetadataField::Players.set_text(&mut game, "1-2").is_ok());
+		assert!(MetadataField::Players.set_text(&mut game, "2-1").is_err());
+		assert!(MetadataField::Players.set_text(&mut game, "two").is_err());
+		assert_eq!(game.players.as_deref(), Some("1-2"));
+
+		MetadataField::Rating.step(&mut game, true);
+		assert_eq!(MetadataField::Rating.value(&game), "4.0 / 5");
+		assert_eq!(game.rating.as_deref(), Some("0.8"));
+
+		MetadataField::Hidden.step(&mut game, true);
+		assert_eq!(game.hidden, Some(true));
+		MetadataField::Hidden.step(&mut game, true);
+		assert_eq!(game.hidden, None);
+	}
+}
@@ -1,35 +1,38 @@
+use super::{KeyboardPurpose, OnScreenKeyboard, Screensaver};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ConfigSettings {
 	SwapConfirm,
 	StartFullscreen,
 	Theme,
 	EnableKeyboard,
-}
-
-impl ConfigSettings {
-	pub fn type_for(&self) -> String {
-		match self {
-			Self::SwapConfirm => "boolean",
-			Self::StartFullscreen => "boolean",
-			Self::Theme => "string",
-			Self::EnableKeyboard => "boolean",
-		}
-		.to_string()
-	}
-}
-
-impl From<usize> for ConfigSettings {
-	fn from(value: usize) -> Self {
-		match value {
-			0 => Self::SwapConfirm,
-			1 => Self::StartFullscreen,
-			2 => Self::Theme,
-			3 => Self::EnableKeyboard,
-			_ => panic!("Invalid menu item"),
-		}
-	}
+	PrimaryControllerOnly,
+	LetterOverlay,
+	PartyMode,
+	UiMode,
+	Volume,
+	PageSize,
+	ScreensaverDelay,
+	Screensaver,
+	ScreensaverLaunch,
+	LogLevel,
+	SortMode,
+	FilterGenre,
+	FilterPlayers,
+	FilterYearFrom,
+	FilterYearTo,
+	FilterRating,
+	FilterDeveloper,
+	FilterPublisher,
+	FilterFavorites,
+	FilterPlayed,
+	RecentlyPlayed,
+	MostPlayed,
+	AllGames,
+	LastAdded,
 }
 
 impl std::fmt::Display for ConfigSettings {
@@ -39,45 +42,35 @@ impl std::fmt::Display for ConfigSettings {
 			ConfigSettings::StartFullscreen => "Start in Fullscreen",
 			ConfigSettings::Theme => "Set Theme",
 			ConfigSettings::EnableKeyboard => "Enable Keyboard",
+			ConfigSettings::PrimaryControllerOnly => "Only First Controller Drives Menus",
+			ConfigSettings::LetterOverlay => "Show Letter When Jumping",
+			ConfigSettings::PartyMode => "Launch Random Games",
+			ConfigSettings::UiMode => "UI Mode",
+			ConfigSettings::Volume => "Volume",
+			ConfigSettings::PageSize => "Games Per Page",
+			ConfigSettings::ScreensaverDelay => "Screensaver After (Minutes)",
+			ConfigSettings::Screensaver => "Screensaver",
+			ConfigSettings::ScreensaverLaunch => "Launch Game From Screensaver",
+			ConfigSettings::LogLevel => "Log Level",
+			ConfigSettings::SortMode => "Sort Games By",
+			ConfigSettings::FilterGenre => "Genre",
+			ConfigSettings::FilterPlayers => "Players",
+			ConfigSettings::FilterYearFrom => "Released From",
+			ConfigSettings::FilterYearTo => "Released Until",
+			ConfigSettings::FilterRating => "Minimum Rating",
+			ConfigSettings::FilterDeveloper => "Developer",
+			ConfigSettings::FilterPublisher => "Publisher",
+			ConfigSettings::FilterFavorites => "Favorites Only",
+			ConfigSettings::FilterPlayed => "Played",
+			ConfigSettings::RecentlyPlayed => "Recently Played",
+			ConfigSettings::MostPlayed => "Most Played",
+			ConfigSettings::AllGames => "All Games",
+			ConfigSettings::LastAdded => "Last Added",
 		})
 	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum MenuItems {
-	Settings,
-	Fullscreen,
-	Exit,
-	Reboot,
-	Shutdown,
-}
-
-impl From<usize> for MenuItems {
-	fn from(value: usize) -> Self {
-		match value {
-			0 => Self::Settings,
-			1 => Self::Fullscreen,
-			2 => Self::Exit,
-			3 => Self::Reboot,
-			4 => Self::Shutdown,
-			_ => panic!("Invalid menu item"),
-		}
-	}
-}
-
-impl std::fmt::Display for MenuItems {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		f.write_str(match self {
-			MenuItems::Settings => "Settings",
-			MenuItems::Fullscreen => "Toggle Fullscreen Window",
-			MenuItems::Exit => "Exit RAWRcade",
-			MenuItems::Reboot => "Reboot System",
-			MenuItems::Shutdown => "Shutdown System",
-		})
-	}
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum InputEvent {
 	Up,
@@ -93,12 +86,69 @@ pub enum InputEvent {
 	PageDown,
 	First,
 	Last,
+	NextLetter,
+	PreviousLetter,
+	Search,
+	ToggleFavorite,
+	/// Jumps to a random game in the current system.
+	RandomGame,
+	/// Jumps to a random game in any system.
+	RandomGameAnywhere,
+	/// Opens or closes the details of the selected game.
+	Details,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerEvent {
+	Connected { id: usize, name: String },
+	Disconnected { id: usize },
+}
+
+/// Sent when an emulator launched from the frontend exits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LauncherExit {
+	pub system: String,
+	pub path: PathBuf,
+	pub success: bool,
+	/// Whether the window was fullscreen before the launch.
+	pub fullscreen: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerEvent {
+	HideLetter {
+		generation: usize,
+	},
+	/// Counts down one second before launching a random game.
+	PartyCountdown {
+		generation: usize,
+	},
+	/// Nothing was pressed for the screensaver delay.
+	Idle,
+	/// Moves the screensaver on to another game.
+	NextSlide {
+		generation: usize,
+	},
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "args", rename_all = "snake_case")]
 pub enum EventType {
 	Input(InputEvent),
+	Controller(ControllerEvent),
+	LauncherExit(LauncherExit),
+	Timer(TimerEvent),
+	FilesChanged(Vec<PathBuf>),
+}
+
+/// Payload of the state events pushed to the frontend. `seq` increases with every update, across
+/// all kinds of state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateUpdate<T> {
+	pub seq: u64,
+	pub data: T,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,11 +158,150 @@ pub struct Event {
 	pub typ: EventType,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Controller {
+	pub id: usize,
+	pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogAction {
+	DeleteGame { system: String, path: PathBuf },
+}
+
+impl DialogAction {
+	pub fn choices(&self) -> Vec<DialogChoice> {
+		match self {
+			Self::DeleteGame { .. } => {
+				vec![
+					DialogChoice::Hide,
+					DialogChoice::Remove,
+					DialogChoice::Cancel,
+				]
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogChoice {
+	Hide,
+	Remove,
+	Cancel,
+}
+
+impl std::fmt::Display for DialogChoice {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			DialogChoice::Hide => "Hide Game",
+			DialogChoice::Remove => "Remove From List",
+			DialogChoice::Cancel => "Cancel",
+		})
+	}
+}
+
+/// A question shown over everything else; while one is open it receives all input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dialog {
+	pub title: String,
+	pub options: Vec<String>,
+	pub selected: usize,
+	pub action: DialogAction,
+}
+
+impl Dialog {
+	pub fn new(title: String, action: DialogAction) -> Self {
+		Self {
+			title,
+			options: action.choices().iter().map(ToString::to_string).collect(),
+			selected: 0,
+			action,
+		}
+	}
+
+	pub fn choice(&self) -> DialogChoice {
+		self.action.choices()[self.selected].clone()
+	}
+}
+
+/// What is missing before there is anything to play, shown by the frontend on first run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SetupStatus {
+	Ready,
+	/// Neither the data directory nor `es_systems.cfg` exist; Ok creates them.
+	MissingDataDirectory {
+		path: PathBuf,
+	},
+	/// The data directory exists without `es_systems.cfg`; Ok creates a starter one.
+	MissingSystems {
+		path: PathBuf,
+	},
+	/// No configured system has a game list with games in it.
+	NoGames {
+		systems: PathBuf,
+		gamelists: PathBuf,
+	},
+	/// The configuration could not be read.
+	Broken {
+		error: String,
+	},
+}
+
+/// The screens the player can be on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum View {
+	GameList,
+	/// The selected game's metadata, media and actions; see [super::GameDetails].
+	Details {
+		selected: usize,
+		scroll: usize,
+	},
+	/// The metadata editor for the selected game; see [super::MetadataEditor].
+	Editor {
+		selected: usize,
+	},
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Orientation {
 	pub system_index: usize,
 	pub gamelist_index: usize,
-	pub menu_active: bool,
-	pub menu_index: Option<usize>,
-	pub menu_item_index: Option<usize>,
+	/// The selected entry at each open level of the menu, outermost first. Empty when the menu
+	/// is closed.
+	pub menu_path: Vec<usize>,
+	/// Views opened over the game list, the last one being shown. Empty when the game list is.
+	pub views: Vec<View>,
+	pub dialog: Option<Dialog>,
+	/// Set briefly after jumping by letter, for the letter overlay.
+	pub letter: Option<char>,
+	/// The on-screen keyboard, while it is open.
+	pub keyboard: Option<OnScreenKeyboard>,
+	/// Seconds until the randomly picked game launches in party mode. Any input cancels it.
+	pub countdown: Option<u32>,
+	/// The buttons pressed so far while entering the PIN that unlocks [crate::UiMode::Full].
+	pub unlock: Option<Vec<InputEvent>>,
+	/// The screensaver, while it is running.
+	pub screensaver: Option<Screensaver>,
+}
+
+impl Orientation {
+	pub fn menu_active(&self) -> bool {
+		!self.menu_path.is_empty()
+	}
+
+	/// Closes the metadata editor and its keyboard, for when games can't be edited anymore.
+	pub fn close_editor(&mut self) {
+		self.views.retain(|x| !matches!(x, View::Editor { .. }));
+		if self
+			.keyboard
+			.as_ref()
+			.is_some_and(|x| x.purpose == KeyboardPurpose::EditMetadata)
+		{
+			self.keyboard = None;
+		}
+	}
 }
@@ -1,51 +1,121 @@
-use crate::{Game, GameList, SystemList};
-use anyhow::Result;
+use crate::{Game, GameList, SystemList, load_custom_collections};
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
 
-pub(crate) fn load_es() -> Result<SystemList> {
-	let root = dirs::home_dir().unwrap_or("/".into()).join(".rawrcade");
-	let mut all_systems = SystemList::from_file(root.join("es_systems.cfg"))
-		.expect("es_systems.cfg missing at ~/.rawrcade");
+// written on first run so there is something to edit
+const STARTER_SYSTEMS: &str = r#"<?xml version="1.0"?>
+<!-- The systems RAWRcade shows, in the EmulationStation format. Add one <system> per emulator.
+In the command, %ROM% is replaced by the shell-escaped path to the game, %ROM_RAW% by the raw path
+and %BASENAME% by the file name. More <command label="..."> elements add alternative emulators,
+the first command being the default. Game lists go in gamelists/<name>/gamelist.xml next to this
+file.
 
-	let gamelist_dir = root.join("gamelists");
+<system>
+	<name>nes</name>
+	<fullname>Nintendo Entertainment System</fullname>
+	<path>~/roms/nes</path>
+	<extension>.nes .zip</extension>
+	<command>retroarch -L /usr/lib/libretro/nestopia_libretro.so %ROM%</command>
+	<platform>nes</platform>
+</system>
+-->
+<systemList>
+</systemList>
+"#;
+
+/// `~/.rawrcade`, unless another data directory is given on the command line.
+pub(crate) fn default_es_root() -> PathBuf {
+	dirs::home_dir().unwrap_or("/".into()).join(".rawrcade")
+}
+
+pub(crate) fn es_systems_file(root: &Path) -> PathBuf {
+	root.join("es_systems.cfg")
+}
+
+pub(crate) fn gamelists_dir(root: &Path) -> PathBuf {
+	root.join("gamelists")
+}
+
+pub(crate) fn collections_dir(root: &Path) -> PathBuf {
+	root.join("collections")
+}
+
+pub(crate) fn themes_dir(root: &Path) -> PathBuf {
+	root.join("themes")
+}
+
+/// Creates the data directory with a starter `es_systems.cfg` and an empty `gamelists`
+/// directory. Existing files are left alone.
+pub(crate) fn create_es_root(root: &Path) -> Result<()> {
+	std::fs::create_dir_all(gamelists_dir(root))?;
+
+	let systems = es_systems_file(root);
+	if !systems.exists() {
+		std::fs::write(systems, STARTER_SYSTEMS)?;
+	}
+
+	Ok(())
+}
+
+/// Loads the systems that have games. A missing `es_systems.cfg` is an empty list, which the
+/// frontend explains on first run.
+pub(crate) fn load_es(root: &Path) -> Result<SystemList> {
+	let systems_file = es_systems_file(root);
+	if !systems_file.exists() {
+		return Ok(SystemList::default());
+	}
+
+	let mut all_systems = SystemList::from_file(systems_file)?;
+
+	let gamelist_dir = gamelists_dir(root);
 
 	if let Ok(dat) = std::fs::metadata(&gamelist_dir) {
 		if dat.is_dir() {
 			let dir = std::fs::read_dir(&gamelist_dir)?;
-			for item in dir {
-				if let Ok(item) = item {
-					if item.metadata()?.is_dir() {
-						let list = item.path().join("gamelist.xml");
-						if std::fs::exists(&list)? {
-							let gamelist = GameList::from_file(list)
-								.unwrap()
-								.game
-								.iter()
-								.map(|x| Into::<Game>::into(x.clone()))
-								.collect::<Vec<Game>>();
-
-							if !gamelist.is_empty() {
-								let name = item.file_name();
-								let name = name.to_string_lossy();
-								let system = all_systems.system.iter_mut().find_map(|x| {
-									if x.name.to_lowercase() == name.to_lowercase() {
-										Some(x)
-									} else {
-										None
-									}
-								});
-
-								if let Some(system) = system {
-									system.gamelist = gamelist;
+			for item in dir.flatten() {
+				if item.metadata()?.is_dir() {
+					let list = item.path().join("gamelist.xml");
+					if std::fs::exists(&list)? {
+						let gamelist = match GameList::from_file(list.clone()) {
+							Ok(gamelist) => gamelist,
+							Err(e) => {
+								tracing::error!("skipping {}: {}", list.display(), e);
+								continue;
+							}
+						};
+
+						let gamelist = gamelist
+							.game
+							.iter()
+							.map(|x| Into::<Game>::into(x.clone()))
+							.collect::<Vec<Game>>();
+
+						if !gamelist.is_empty() {
+							let name = item.file_name();
+							let name = name.to_string_lossy();
+							let system = all_systems.system.iter_mut().find_map(|x| {
+								if x.name.to_lowercase() == name.to_lowercase() {
+									Some(x)
+								} else {
+									None
 								}
+							});
+
+							if let Some(system) = system {
+								system.games = gamelist;
+								system.gamelist_path = Some(list);
+								system.read_rom_times();
+								system.refresh();
 							}
 						}
 					}
 				}
 			}
 		} else {
-			panic!(
-				"~/.rawrcade/gamelists is not a directory. If you symlinked it, please symlink the full ES-DE or .emulationstation directory."
-			)
+			return Err(anyhow!(
+				"{} is not a directory. If you symlinked it, please symlink the full ES-DE or .emulationstation directory.",
+				gamelist_dir.display()
+			));
 		}
 	}
 
@@ -53,7 +123,7 @@ pub(crate) fn load_es() -> Result<SystemList> {
 		.system
 		.iter()
 		.filter_map(|x| {
-			if x.gamelist.is_empty() {
+			if x.games.is_empty() {
 				None
 			} else {
 				Some(x.clone())
@@ -61,5 +131,7 @@ pub(crate) fn load_es() -> Result<SystemList> {
 		})
 		.collect();
 
+	all_systems.custom = load_custom_collections(&collections_dir(root));
+
 	Ok(all_systems)
 }
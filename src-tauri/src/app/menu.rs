@@ -0,0 +1,867 @@
+use super::{
+	APP_HANDLE, App, ConfigSettings, InputEvent, KeyboardPurpose, selected_rom, themes_dir,
+};
+use crate::{
+	AutoCollection, Config, Game, LOG_LEVEL, LogLevel, ScreensaverType, SortMode, UiMode,
+	discover_themes, field_values, genres,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use tauri::Manager;
+use tracing_subscriber::filter::LevelFilter;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuAction {
+	Fullscreen,
+	Exit,
+	Reboot,
+	Shutdown,
+	ClearFilters,
+	/// Adds the selected game to the custom collection, or removes it if it is in it.
+	ToggleCollection {
+		name: String,
+	},
+	/// Asks for a name and makes a custom collection with the selected game.
+	NewCollection,
+	/// Asks for the PIN that goes back to [UiMode::Full].
+	Unlock,
+}
+
+impl std::fmt::Display for MenuAction {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			MenuAction::Fullscreen => "Toggle Fullscreen Window",
+			MenuAction::Exit => "Exit RAWRcade",
+			MenuAction::Reboot => "Reboot System",
+			MenuAction::Shutdown => "Shutdown System",
+			MenuAction::ClearFilters => "Clear Filters",
+			MenuAction::ToggleCollection { name } => name,
+			MenuAction::NewCollection => "Add to New Collection",
+			MenuAction::Unlock => "Unlock Full Mode",
+		})
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MenuEntryKind {
+	/// An on/off setting, flipped with Ok, Left or Right.
+	Toggle {
+		setting: ConfigSettings,
+		value: bool,
+	},
+	/// A setting with a fixed set of values, cycled with Ok, Left or Right.
+	Choice {
+		setting: ConfigSettings,
+		options: Vec<String>,
+		selected: usize,
+	},
+	/// A whole number between `min` and `max`, changed by `step` with Left or Right.
+	Range {
+		setting: ConfigSettings,
+		value: i64,
+		min: i64,
+		max: i64,
+		step: i64,
+	},
+	/// A setting picked from options only known at runtime. Ok opens the options as a level of
+	/// [MenuEntryKind::Select] entries.
+	List {
+		setting: ConfigSettings,
+		value: String,
+		options: Vec<ListOption>,
+	},
+	/// One of the options of a [MenuEntryKind::List]; Ok picks it and goes back.
+	Select {
+		setting: ConfigSettings,
+		value: String,
+		selected: bool,
+	},
+	Action {
+		action: MenuAction,
+	},
+	/// Opens another level. Only the level being shown is sent to the frontend.
+	Submenu {
+		#[serde(skip)]
+		entries: Vec<MenuEntry>,
+	},
+	/// Read-only information.
+	Text {
+		value: String,
+	},
+}
+
+impl MenuEntryKind {
+	/// The level this entry opens, if any.
+	fn level(&self) -> Option<Vec<MenuEntry>> {
+		match self {
+			Self::Submenu { entries } => Some(entries.clone()),
+			Self::List {
+				setting,
+				value,
+				options,
+			} => Some(
+				options
+					.iter()
+					.map(|x| {
+						MenuEntry::new(
+							x.label.clone(),
+							Self::Select {
+								setting: *setting,
+								value: x.value.clone(),
+								selected: x.value == *value,
+							},
+						)
+					})
+					.collect(),
+			),
+			_ => None,
+		}
+	}
+}
+
+/// An option of a [MenuEntryKind::List], stored as `value` and shown as `label`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListOption {
+	pub value: String,
+	pub label: String,
+}
+
+impl From<String> for ListOption {
+	fn from(value: String) -> Self {
+		Self {
+			label: value.clone(),
+			value,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuEntry {
+	pub label: String,
+	#[serde(flatten)]
+	pub kind: MenuEntryKind,
+}
+
+impl MenuEntry {
+	pub fn new(label: impl Into<String>, kind: MenuEntryKind) -> Self {
+		Self {
+			label: label.into(),
+			kind,
+		}
+	}
+}
+
+/// The open level of the menu, as shown by the theme.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuView {
+	pub title: String,
+	pub entries: Vec<MenuEntry>,
+	pub selected: usize,
+	/// How many levels deep the view is; 1 is the top level.
+	pub depth: usize,
+}
+
+impl ConfigSettings {
+	pub fn all() -> Vec<Self> {
+		vec![
+			Self::SwapConfirm,
+			Self::StartFullscreen,
+			Self::Theme,
+			Self::EnableKeyboard,
+			Self::PrimaryControllerOnly,
+			Self::LetterOverlay,
+			Self::Volume,
+			Self::PageSize,
+			Self::ScreensaverDelay,
+			Self::Screensaver,
+			Self::ScreensaverLaunch,
+			Self::LogLevel,
+			Self::PartyMode,
+			Self::UiMode,
+			Self::SortMode,
+		]
+	}
+
+	/// The automatic collections that can be turned on or off.
+	pub fn collections() -> Vec<Self> {
+		vec![
+			Self::RecentlyPlayed,
+			Self::MostPlayed,
+			Self::AllGames,
+			Self::LastAdded,
+		]
+	}
+
+	/// The collection this setting turns on or off.
+	fn collection(&self) -> Option<AutoCollection> {
+		match self {
+			Self::RecentlyPlayed => Some(AutoCollection::RecentlyPlayed),
+			Self::MostPlayed => Some(AutoCollection::MostPlayed),
+			Self::AllGames => Some(AutoCollection::AllGames),
+			Self::LastAdded => Some(AutoCollection::LastAdded),
+			_ => None,
+		}
+	}
+
+	/// The conditions of the current system's filter.
+	pub fn filters() -> Vec<Self> {
+		vec![
+			Self::FilterGenre,
+			Self::FilterPlayers,
+			Self::FilterYearFrom,
+			Self::FilterYearTo,
+			Self::FilterRating,
+			Self::FilterDeveloper,
+			Self::FilterPublisher,
+			Self::FilterFavorites,
+			Self::FilterPlayed,
+		]
+	}
+
+	/// The menu entry for this setting. `system` is the name of the current system, for settings
+	/// kept per system. `options` are the choices of list settings, which are computed at runtime
+	/// by [App::setting_options].
+	pub fn entry(&self, config: &Config, system: &str, options: Vec<ListOption>) -> MenuEntry {
+		let toggle = |value| MenuEntryKind::Toggle {
+			setting: *self,
+			value,
+		};
+
+		let range = |value: i64, min, max, step| MenuEntryKind::Range {
+			setting: *self,
+			value,
+			min,
+			max,
+			step,
+		};
+
+		let choice = |options: Vec<String>, selected| MenuEntryKind::Choice {
+			setting: *self,
+			options,
+			selected,
+		};
+
+		let list = |value: &Option<String>| MenuEntryKind::List {
+			setting: *self,
+			value: value.clone().unwrap_or(ANY.to_string()),
+			options: options.clone(),
+		};
+
+		let filter = config.filter(system);
+
+		let kind = match self {
+			Self::SwapConfirm => toggle(config.swap_confirm),
+			Self::StartFullscreen => toggle(config.start_fullscreen),
+			Self::EnableKeyboard => toggle(config.enable_keyboard),
+			Self::PrimaryControllerOnly => toggle(config.primary_controller_only),
+			Self::LetterOverlay => toggle(config.letter_overlay),
+			Self::PartyMode => toggle(config.party_mode),
+			Self::Volume => range(config.volume as i64, 0, 100, 5),
+			Self::PageSize => range(config.page_size as i64, 5, 50, 5),
+			Self::ScreensaverDelay => range(config.screensaver_delay as i64, 0, 60, 1),
+			Self::LogLevel => MenuEntryKind::Choice {
+				setting: *self,
+				options: LogLevel::all().iter().map(ToString::to_string).collect(),
+				selected: LogLevel::all()
+					.iter()
+					.position(|x| *x == config.log_level)
+					.unwrap_or_default(),
+			},
+			Self::Screensaver => MenuEntryKind::Choice {
+				setting: *self,
+				options: ScreensaverType::all()
+					.iter()
+					.map(ToString::to_string)
+					.collect(),
+				selected: ScreensaverType::all()
+					.iter()
+					.position(|x| *x == config.screensaver)
+					.unwrap_or_default(),
+			},
+			Self::ScreensaverLaunch => toggle(config.screensaver_launch),
+			Self::UiMode => MenuEntryKind::Choice {
+				setting: *self,
+				options: UiMode::all().iter().map(ToString::to_string).collect(),
+				selected: UiMode::all()
+					.iter()
+					.position(|x| *x == config.ui_mode)
+					.unwrap_or_default(),
+			},
+			Self::SortMode => MenuEntryKind::Choice {
+				setting: *self,
+				options: SortMode::all().iter().map(ToString::to_string).collect(),
+				selected: SortMode::all()
+					.iter()
+					.position(|x| *x == config.sort_mode(system))
+					.unwrap_or_default(),
+			},
+			Self::Theme => MenuEntryKind::List {
+				setting: *self,
+				value: config.theme.clone().unwrap_or_default(),
+				options: options.clone(),
+			},
+			Self::FilterGenre => list(&filter.genre),
+			Self::FilterDeveloper => list(&filter.developer),
+			Self::FilterPublisher => list(&filter.publisher),
+			Self::FilterPlayers => choice(
+				any_or((1..=MAX_PLAYERS).map(|x| format!("{x}+"))),
+				filter.players.unwrap_or_default().min(MAX_PLAYERS) as usize,
+			),
+			Self::FilterRating => choice(
+				any_or((1..=5).map(|x| format!("{x}+ Stars"))),
+				filter.min_rating.unwrap_or_default().min(5) as usize,
+			),
+			Self::FilterPlayed => choice(
+				any_or(["Played".to_string(), "Unplayed".to_string()]),
+				match filter.played {
+					None => 0,
+					Some(true) => 1,
+					Some(false) => 2,
+				},
+			),
+			Self::FilterYearFrom => range(
+				filter.year_from.map_or(MIN_YEAR, i64::from),
+				MIN_YEAR,
+				MAX_YEAR,
+				1,
+			),
+			Self::FilterYearTo => range(
+				filter.year_to.map_or(MAX_YEAR, i64::from),
+				MIN_YEAR,
+				MAX_YEAR,
+				1,
+			),
+			Self::FilterFavorites => toggle(filter.favorites_only),
+			Self::RecentlyPlayed | Self::MostPlayed | Self::AllGames | Self::LastAdded => toggle(
+				self.collection()
+					.is_some_and(|x| config.collections.contains(&x)),
+			),
+		};
+
+		MenuEntry::new(self.to_string(), kind)
+	}
+
+	/// Moves the setting to its next value, or its previous one when `forward` is false. Ranges
+	/// stop at their bounds, other settings wrap around.
+	pub fn cycle(&self, config: &mut Config, system: &str, forward: bool) {
+		let entry = self.entry(config, system, Vec::new());
+
+		match entry.kind {
+			MenuEntryKind::Toggle { .. } => self.set_bool(config, system),
+			MenuEntryKind::Range {
+				value,
+				min,
+				max,
+				step,
+				..
+			} => {
+				let value = if forward { value + step } else { value - step };
+				self.set_int(config, system, value.clamp(min, max));
+			}
+			MenuEntryKind::Choice {
+				options, selected, ..
+			} => {
+				let len = options.len();
+				let next = if forward {
+					(selected + 1) % len
+				} else {
+					(selected + len - 1) % len
+				};
+
+				match self {
+					Self::LogLevel => config.log_level = LogLevel::all()[next].clone(),
+					Self::UiMode => config.ui_mode = UiMode::all()[next],
+					Self::Screensaver => config.screensaver = ScreensaverType::all()[next],
+					Self::SortMode if !system.is_empty() => {
+						config
+							.sort
+							.insert(system.to_string(), SortMode::all()[next]);
+					}
+					Self::FilterPlayers if !system.is_empty() => {
+						config.filter_mut(system).players = (next > 0).then_some(next as u32);
+					}
+					Self::FilterRating if !system.is_empty() => {
+						config.filter_mut(system).min_rating = (next > 0).then_some(next as u32);
+					}
+					Self::FilterPlayed if !system.is_empty() => {
+						config.filter_mut(system).played = [None, Some(true), Some(false)][next];
+					}
+					_ => {}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	/// Sets a list setting to one of its options.
+	pub fn pick(&self, config: &mut Config, system: &str, value: &str) {
+		let any = (value != ANY).then(|| value.to_string());
+
+		match self {
+			Self::Theme => config.theme = (!value.is_empty()).then(|| value.to_string()),
+			Self::FilterGenre if !system.is_empty() => config.filter_mut(system).genre = any,
+			Self::FilterDeveloper if !system.is_empty() => {
+				config.filter_mut(system).developer = any
+			}
+			Self::FilterPublisher if !system.is_empty() => {
+				config.filter_mut(system).publisher = any
+			}
+			_ => {}
+		}
+	}
+
+	fn set_bool(&self, config: &mut Config, system: &str) {
+		match self {
+			Self::SwapConfirm => config.swap_confirm = !config.swap_confirm,
+			Self::StartFullscreen => config.start_fullscreen = !config.start_fullscreen,
+			Self::EnableKeyboard => config.enable_keyboard = !config.enable_keyboard,
+			Self::PrimaryControllerOnly => {
+				config.primary_controller_only = !config.primary_controller_only
+			}
+			Self::LetterOverlay => config.letter_overlay = !config.letter_overlay,
+			Self::PartyMode => config.party_mode = !config.party_mode,
+			Self::ScreensaverLaunch => config.screensaver_launch = !config.screensaver_launch,
+			Self::FilterFavorites if !system.is_empty() => {
+				let filter = config.filter_mut(system);
+				filter.favorites_only = !filter.favorites_only;
+			}
+			Self::RecentlyPlayed | Self::MostPlayed | Self::AllGames | Self::LastAdded => {
+				if let Some(collection) = self.collection() {
+					if !config.collections.remove(&collection) {
+						config.collections.insert(collection);
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn set_int(&self, config: &mut Config, system: &str, value: i64) {
+		match self {
+			Self::Volume => config.volume = value as u8,
+			Self::PageSize => config.page_size = value as usize,
+			Self::ScreensaverDelay => config.screensaver_delay = value as u64,
+			// the ends of the range mean no bound
+			Self::FilterYearFrom if !system.is_empty() => {
+				config.filter_mut(system).year_from = (value > MIN_YEAR).then_some(value as u32)
+			}
+			Self::FilterYearTo if !system.is_empty() => {
+				config.filter_mut(system).year_to = (value < MAX_YEAR).then_some(value as u32)
+			}
+			_ => {}
+		}
+	}
+}
+
+// the option of filter conditions that turns them off
+const ANY: &str = "Any";
+const MAX_PLAYERS: u32 = 4;
+const MIN_YEAR: i64 = 1970;
+const MAX_YEAR: i64 = 2030;
+
+fn any_or(options: impl IntoIterator<Item = String>) -> Vec<String> {
+	let mut all = vec![ANY.to_string()];
+	all.extend(options);
+	all
+}
+
+// shown in the theme list for the theme bundled with the frontend, which is stored as no theme.
+// No directory is named "", so it can't be mistaken for an installed theme.
+const BUILT_IN_THEME: &str = "Built-in";
+
+impl App {
+	/// The options of list settings, which depend on what is installed and, for filters, on the
+	/// `games` of the current system.
+	pub fn setting_options(&self, setting: ConfigSettings, games: &[Game]) -> Vec<ListOption> {
+		let text = |field: fn(&Game) -> &Option<String>| {
+			any_or(field_values(games, |x| {
+				field(x)
+					.iter()
+					.map(|x| x.trim().to_string())
+					.filter(|x| !x.is_empty())
+					.collect()
+			}))
+		};
+
+		let options = match setting {
+			ConfigSettings::Theme => {
+				let mut themes = vec![ListOption {
+					value: String::new(),
+					label: BUILT_IN_THEME.to_string(),
+				}];
+				themes.extend(
+					discover_themes(&themes_dir(&self.data_dir))
+						.into_iter()
+						.map(|x| ListOption {
+							value: x.id,
+							label: x.manifest.name,
+						}),
+				);
+				return themes;
+			}
+			ConfigSettings::FilterGenre => any_or(field_values(games, genres)),
+			ConfigSettings::FilterDeveloper => text(|x| &x.developer),
+			ConfigSettings::FilterPublisher => text(|x| &x.publisher),
+			_ => Vec::new(),
+		};
+
+		options.into_iter().map(ListOption::from).collect()
+	}
+
+	/// Every setting with its current value and the metadata needed to show it.
+	pub async fn settings_menu(&self) -> Vec<MenuEntry> {
+		let system = self.current_system_name().await;
+		let config = self.config.lock().await;
+		ConfigSettings::all()
+			.iter()
+			.map(|x| x.entry(&config, &system, self.setting_options(*x, &[])))
+			.collect()
+	}
+
+	/// The filter of the current system, with the genres, developers and publishers found in its
+	/// games to choose from.
+	pub async fn filter_menu(&self) -> Vec<MenuEntry> {
+		let (system, options) = {
+			let orientation = self.orientation.lock().await;
+			let systems = self.all_systems.lock().await;
+			let Some(system) = systems.system.get(orientation.system_index) else {
+				return Vec::new();
+			};
+
+			let options: Vec<Vec<ListOption>> = ConfigSettings::filters()
+				.iter()
+				.map(|x| self.setting_options(*x, &system.games))
+				.collect();
+
+			(system.name.clone(), options)
+		};
+
+		let config = self.config.lock().await;
+		let mut entries: Vec<MenuEntry> = ConfigSettings::filters()
+			.iter()
+			.zip(options)
+			.map(|(x, options)| x.entry(&config, &system, options))
+			.collect();
+
+		entries.push(MenuEntry::new(
+			MenuAction::ClearFilters.to_string(),
+			MenuEntryKind::Action {
+				action: MenuAction::ClearFilters,
+			},
+		));
+
+		entries
+	}
+
+	/// Adds the selected game to, or removes it from, each custom collection. Empty without a
+	/// selected game.
+	pub async fn custom_collections_menu(&self) -> Vec<MenuEntry> {
+		let orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+		let Some((rom, _)) = selected_rom(&orientation, &systems) else {
+			return Vec::new();
+		};
+
+		let mut entries: Vec<MenuEntry> = systems
+			.custom
+			.iter()
+			.map(|x| {
+				let label = if x.contains(&rom) {
+					format!("Remove from {}", x.name)
+				} else {
+					format!("Add to {}", x.name)
+				};
+
+				MenuEntry::new(
+					label,
+					MenuEntryKind::Action {
+						action: MenuAction::ToggleCollection {
+							name: x.name.clone(),
+						},
+					},
+				)
+			})
+			.collect();
+
+		entries.push(MenuEntry::new(
+			MenuAction::NewCollection.to_string(),
+			MenuEntryKind::Action {
+				action: MenuAction::NewCollection,
+			},
+		));
+
+		entries
+	}
+
+	/// Builds the whole menu from the current configuration and controllers. Outside of
+	/// [UiMode::Full] it only lists controllers and a way back.
+	pub async fn menu_tree(&self) -> Vec<MenuEntry> {
+		if self.config.lock().await.ui_mode.is_restricted() {
+			return vec![
+				self.controllers_entry().await,
+				MenuEntry::new(
+					MenuAction::Unlock.to_string(),
+					MenuEntryKind::Action {
+						action: MenuAction::Unlock,
+					},
+				),
+			];
+		}
+
+		let settings = self.settings_menu().await;
+		let filters = self.filter_menu().await;
+		let custom = self.custom_collections_menu().await;
+		let collections = {
+			let config = self.config.lock().await;
+			ConfigSettings::collections()
+				.iter()
+				.map(|x| x.entry(&config, "", Vec::new()))
+				.collect()
+		};
+
+		let mut tree = vec![MenuEntry::new(
+			"Settings",
+			MenuEntryKind::Submenu { entries: settings },
+		)];
+
+		if !filters.is_empty() {
+			tree.push(MenuEntry::new(
+				"Filter Games",
+				MenuEntryKind::Submenu { entries: filters },
+			));
+		}
+
+		if !custom.is_empty() {
+			tree.push(MenuEntry::new(
+				"Custom Collections",
+				MenuEntryKind::Submenu { entries: custom },
+			));
+		}
+
+		tree.extend([
+			MenuEntry::new(
+				"Game Collections",
+				MenuEntryKind::Submenu {
+					entries: collections,
+				},
+			),
+			self.controllers_entry().await,
+		]);
+
+		tree.extend(
+			[
+				MenuAction::Fullscreen,
+				MenuAction::Exit,
+				MenuAction::Reboot,
+				MenuAction::Shutdown,
+			]
+			.map(|action| MenuEntry::new(action.to_string(), MenuEntryKind::Action { action })),
+		);
+
+		tree
+	}
+
+	async fn controllers_entry(&self) -> MenuEntry {
+		let entries = self
+			.controllers_menu()
+			.await
+			.into_iter()
+			.map(|x| {
+				MenuEntry::new(
+					x,
+					MenuEntryKind::Text {
+						value: String::new(),
+					},
+				)
+			})
+			.collect();
+
+		MenuEntry::new("Controllers", MenuEntryKind::Submenu { entries })
+	}
+
+	/// What the frontend shows for `path`, or `None` when the menu is closed.
+	pub async fn menu_view(&self, path: &[usize]) -> Option<MenuView> {
+		let (selected, parents) = path.split_last()?;
+
+		let mut title = "Menu".to_string();
+		let mut entries = self.menu_tree().await;
+
+		for index in parents {
+			let entry = entries.get(*index)?;
+			title = entry.label.clone();
+			entries = entry.kind.level()?;
+		}
+
+		Some(MenuView {
+			title,
+			entries,
+			selected: *selected,
+			depth: path.len(),
+		})
+	}
+
+	pub(crate) async fn event_menu(&self, event: InputEvent) {
+		let system = self.current_system_name().await;
+		let path = self.orientation.lock().await.menu_path.clone();
+		let Some(view) = self.menu_view(&path).await else {
+			// the tree changed under the path, e.g. a controller went away
+			self.orientation.lock().await.menu_path.clear();
+			return;
+		};
+
+		let len = view.entries.len();
+		let entry = view.entries.get(view.selected).cloned();
+
+		let mut orientation = self.orientation.lock().await;
+		let selected = orientation.menu_path.last_mut().unwrap();
+
+		match event {
+			InputEvent::Up if len > 0 => *selected = (*selected + len - 1) % len,
+			InputEvent::Down if len > 0 => *selected = (*selected + 1) % len,
+			InputEvent::First => *selected = 0,
+			InputEvent::Last => *selected = len.saturating_sub(1),
+			InputEvent::Cancel => {
+				orientation.menu_path.pop();
+			}
+			InputEvent::Menu => orientation.menu_path.clear(),
+			InputEvent::Ok | InputEvent::Left | InputEvent::Right => {
+				let forward = event != InputEvent::Left;
+
+				match entry.map(|x| x.kind) {
+					Some(MenuEntryKind::Submenu { entries }) => {
+						if event == InputEvent::Ok && !entries.is_empty() {
+							orientation.menu_path.push(0);
+						}
+					}
+					Some(MenuEntryKind::List { value, options, .. }) => {
+						if event == InputEvent::Ok && !options.is_empty() {
+							let current = options.iter().position(|x| x.value == value);
+							orientation.menu_path.push(current.unwrap_or_default());
+						}
+					}
+					Some(MenuEntryKind::Select { setting, value, .. }) => {
+						if event == InputEvent::Ok {
+							orientation.menu_path.pop();
+							drop(orientation);
+							self.change_setting(|config| setting.pick(config, &system, &value))
+								.await;
+						}
+					}
+					Some(MenuEntryKind::Range { setting, .. }) => {
+						if event != InputEvent::Ok {
+							drop(orientation);
+							self.change_setting(|config| setting.cycle(config, &system, forward))
+								.await;
+						}
+					}
+					Some(MenuEntryKind::Toggle { setting, .. })
+					| Some(MenuEntryKind::Choice { setting, .. }) => {
+						drop(orientation);
+						self.change_setting(|config| setting.cycle(config, &system, forward))
+							.await;
+					}
+					Some(MenuEntryKind::Action { action }) => {
+						if event == InputEvent::Ok {
+							drop(orientation);
+							self.run_menu_action(action).await;
+						}
+					}
+					Some(MenuEntryKind::Text { .. }) | None => {}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	async fn change_setting(&self, f: impl FnOnce(&mut Config)) {
+		let (collections, ui_mode) = {
+			let mut config = self.config.lock().await;
+			let collections = config.collections.clone();
+			let ui_mode = config.ui_mode;
+			let log_level = config.log_level.clone();
+			f(&mut config);
+			config.filters.retain(|_, x| !x.is_empty());
+
+			self.primary_controller_only
+				.store(config.primary_controller_only, Ordering::SeqCst);
+
+			if config.log_level != log_level {
+				let level: tracing::Level = config.log_level.clone().into();
+				if let Some(Err(e)) = LOG_LEVEL.get().map(|x| x.reload(LevelFilter::from(level))) {
+					tracing::error!("could not change the log level: {}", e);
+				}
+			}
+
+			(config.collections != collections, config.ui_mode != ui_mode)
+		};
+
+		if ui_mode {
+			// the menu is a different one now, games can't be edited anymore, and the mode has to
+			// hold across a power cut
+			let restricted = self.config.lock().await.ui_mode.is_restricted();
+			let mut orientation = self.orientation.lock().await;
+			orientation.menu_path.clear();
+			if restricted {
+				orientation.close_editor();
+			}
+			drop(orientation);
+
+			if let Err(e) = self.save_config().await {
+				tracing::error!(
+					"could not write file: {}: {}",
+					self.config_filename.display(),
+					e
+				);
+			}
+		}
+
+		self.apply_system_settings(collections || ui_mode).await;
+	}
+
+	async fn run_menu_action(&self, action: MenuAction) {
+		match action {
+			MenuAction::Reboot => {
+				self.save_on_exit().await;
+
+				std::process::Command::new("reboot")
+					.status()
+					.expect("could not reboot");
+			}
+			MenuAction::Shutdown => {
+				self.save_on_exit().await;
+
+				std::process::Command::new("poweroff")
+					.status()
+					.expect("could not poweroff");
+			}
+			MenuAction::Fullscreen => {
+				if let Some(app_handle) = APP_HANDLE.get() {
+					if let Some(window) = app_handle.get_window("main") {
+						window
+							.set_fullscreen(
+								!window.is_fullscreen().expect("could not toggle fullscreen"),
+							)
+							.expect("Could not unset fullscreen state");
+					}
+				}
+			}
+			MenuAction::Exit => self.quit().await,
+			MenuAction::ClearFilters => {
+				let system = self.current_system_name().await;
+				self.change_setting(|config| {
+					config.filters.remove(&system);
+				})
+				.await;
+			}
+			MenuAction::ToggleCollection { name } => self.update_collection(&name, false).await,
+			MenuAction::NewCollection => self.open_keyboard(KeyboardPurpose::NewCollection).await,
+			MenuAction::Unlock => {
+				let mut orientation = self.orientation.lock().await;
+				orientation.menu_path.clear();
+				orientation.unlock = Some(Vec::new());
+			}
+		}
+	}
+}
@@ -1,23 +1,80 @@
-use crate::{APP_HANDLE, Config, DEFAULT_CONFIG_FILENAME, Game, GameList, SystemList};
-use anyhow::Result;
+use crate::{
+	APP_HANDLE, ActiveTheme, Cli, Config, CustomCollection, DEFAULT_CONFIG_FILENAME,
+	DEFAULT_STATE_FILENAME, EsThemeOptions, FileWatcher, Game, NavigationState, System, SystemList,
+	ThemeFormat, ThemeLayout, UiMode, discover_themes, load_es_theme,
+};
+use anyhow::{Result, anyhow};
+use serde::Serialize;
 use std::{
-	path::PathBuf,
+	path::{Path, PathBuf},
 	sync::{
 		Arc,
-		atomic::{AtomicBool, Ordering},
+		atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
 	},
-	time::Duration,
+	time::{Duration, Instant, SystemTime},
 };
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tokio::sync::{
 	Mutex,
 	mpsc::{Receiver, Sender, channel},
 };
 
+mod details;
+mod editor;
 mod enums;
 mod es_support;
+mod menu;
+mod screensaver;
+mod search;
+pub use self::details::*;
+pub use self::editor::*;
 pub use self::enums::*;
 pub(crate) use self::es_support::*;
+pub use self::menu::*;
+pub use self::screensaver::*;
+pub use self::search::*;
+
+/// Seconds before a game picked at random launches in party mode.
+const PARTY_COUNTDOWN: u32 = 5;
+
+/// What the player has to do before `systems`, loaded from `data_dir`, have games to show.
+fn check_setup(data_dir: &Path, systems: &SystemList) -> SetupStatus {
+	if !systems.system.is_empty() {
+		return SetupStatus::Ready;
+	}
+
+	if !es_systems_file(data_dir).exists() {
+		return match data_dir.exists() {
+			true => SetupStatus::MissingSystems {
+				path: es_systems_file(data_dir),
+			},
+			false => SetupStatus::MissingDataDirectory {
+				path: data_dir.to_path_buf(),
+			},
+		};
+	}
+
+	match load_es(data_dir) {
+		Err(e) => SetupStatus::Broken {
+			error: e.to_string(),
+		},
+		Ok(_) => SetupStatus::NoGames {
+			systems: es_systems_file(data_dir),
+			gamelists: gamelists_dir(data_dir),
+		},
+	}
+}
+
+/// The state most recently pushed to the frontend.
+#[derive(Debug, Default)]
+struct Published {
+	orientation: Option<Orientation>,
+	setup: Option<SetupStatus>,
+	menu: Option<MenuView>,
+	settings: Config,
+	controllers: Vec<String>,
+	systems: usize,
+}
 
 #[derive(Debug, Clone)]
 pub struct App {
@@ -25,417 +82,1438 @@ pub struct App {
 	pub all_systems: Arc<Mutex<SystemList>>,
 	pub orientation: Arc<Mutex<Orientation>>,
 	pub input_send: Sender<InputEvent>,
+	pub controller_send: Sender<ControllerEvent>,
+	pub controllers: Arc<Mutex<Vec<Controller>>>,
+	pub primary_controller_only: Arc<AtomicBool>,
+	pub event_send: Sender<Event>,
+	pub watcher: FileWatcher,
 
 	config_filename: PathBuf,
+	state_filename: PathBuf,
+	/// Where `es_systems.cfg`, the gamelists, collections and themes are.
+	data_dir: PathBuf,
+	/// What the player has to do before there are games, checked when the files change.
+	setup: Arc<Mutex<SetupStatus>>,
+	navigation: Arc<Mutex<NavigationState>>,
+	letter_generation: Arc<AtomicUsize>,
+	countdown_generation: Arc<AtomicUsize>,
+	slide_generation: Arc<AtomicUsize>,
+	/// When the player last pressed anything, for the screensaver.
+	last_input: Arc<Mutex<Instant>>,
+	systems_generation: Arc<AtomicUsize>,
+	state_seq: Arc<AtomicU64>,
+	published: Arc<Mutex<Published>>,
 	input_recv: Arc<Mutex<Receiver<InputEvent>>>,
+	controller_recv: Arc<Mutex<Receiver<ControllerEvent>>>,
+	event_recv: Arc<Mutex<Receiver<Event>>>,
 	ignore_events: Arc<AtomicBool>,
 }
 
 impl Default for App {
 	fn default() -> Self {
-		let mut all_systems = SystemList::from_file("test-systems.xml".into()).unwrap();
-
-		let gamelist = GameList::from_file("test-gamelist.xml".into())
-			.unwrap()
-			.game
-			.iter()
-			.map(|x| Into::<Game>::into(x.clone()))
-			.collect::<Vec<Game>>();
-
-		for x in &mut all_systems.system {
-			x.gamelist = gamelist.clone()
-		}
-
 		let (s, r) = channel(1000);
+		let (cs, cr) = channel(100);
+		let (es, er) = channel(100);
 
 		let path = dirs::config_dir().unwrap_or(dirs::home_dir().unwrap_or("/".into()));
 
 		Self {
 			config_filename: path.join(DEFAULT_CONFIG_FILENAME),
+			state_filename: path.join(DEFAULT_STATE_FILENAME),
+			data_dir: default_es_root(),
+			setup: Arc::new(Mutex::new(SetupStatus::Ready)),
+			navigation: Default::default(),
 			config: Arc::new(Mutex::new(Config::default())),
 			input_send: s,
 			input_recv: Arc::new(Mutex::new(r)),
-			all_systems: Arc::new(Mutex::new(all_systems)),
+			controller_send: cs,
+			controller_recv: Arc::new(Mutex::new(cr)),
+			event_send: es,
+			event_recv: Arc::new(Mutex::new(er)),
+			watcher: Default::default(),
+			controllers: Default::default(),
+			primary_controller_only: Default::default(),
+			all_systems: Default::default(),
 			orientation: Arc::new(Mutex::new(Orientation::default())),
 			ignore_events: Default::default(),
+			letter_generation: Default::default(),
+			countdown_generation: Default::default(),
+			slide_generation: Default::default(),
+			last_input: Arc::new(Mutex::new(Instant::now())),
+			systems_generation: Default::default(),
+			state_seq: Default::default(),
+			published: Default::default(),
 		}
 	}
 }
 
 impl App {
-	pub fn new(config_filename: Option<&PathBuf>) -> Result<Self> {
+	/// The application as configured, with the command line's overrides. Fails when `--config`
+	/// can't be read or `--system` names a system that isn't there.
+	pub fn new(cli: &Cli) -> Result<Self> {
 		let mut this = Self::default();
+		if let Some(config_filename) = &cli.config {
+			this.config_filename = config_filename.clone();
+		}
+		if let Some(data_dir) = &cli.data_dir {
+			this.data_dir = data_dir.clone();
+		}
+
+		// a config file asked for by name has to be there; saving defaults over it would lose it
+		let config = match Config::from_file(&this.config_filename) {
+			Ok(config) => config,
+			Err(e) if cli.config.is_some() => {
+				return Err(anyhow!(
+					"could not read {}: {}",
+					this.config_filename.display(),
+					e
+				));
+			}
+			Err(_) => Config::default(),
+		};
+
+		let mut systems = load_es(&this.data_dir).unwrap_or_else(|e| {
+			tracing::error!("could not load systems: {}", e);
+			SystemList::default()
+		});
+
+		systems.refresh_collections(&config);
+		systems.apply_config(&config);
+
+		this.primary_controller_only
+			.store(config.primary_controller_only, Ordering::SeqCst);
 
-		if let Ok(config) = Config::from_file(config_filename.unwrap_or(&this.config_filename)) {
-			this.config = Arc::new(Mutex::new(config))
+		let mut navigation = NavigationState::from_file(&this.state_filename).unwrap_or_default();
+		if let Some(name) = &cli.system {
+			let system = systems
+				.system
+				.iter()
+				.find(|x| x.name.eq_ignore_ascii_case(name))
+				.ok_or_else(|| anyhow!("there is no system named {}", name))?;
+			navigation.system = Some(system.name.clone());
+			navigation.views.clear();
 		}
 
-		this.all_systems = Arc::new(Mutex::new(load_es()?));
+		let mut orientation = navigation.restore(&systems);
+		if config.ui_mode.is_restricted() {
+			orientation.close_editor();
+		}
+
+		this.setup = Arc::new(Mutex::new(check_setup(&this.data_dir, &systems)));
+		this.config = Arc::new(Mutex::new(config));
+		this.orientation = Arc::new(Mutex::new(orientation));
+		this.navigation = Arc::new(Mutex::new(navigation));
+
+		this.all_systems = Arc::new(Mutex::new(systems));
 
 		Ok(this)
 	}
 
-	pub fn menu(&self) -> Vec<MenuItems> {
-		vec![
-			MenuItems::Settings,
-			MenuItems::Fullscreen,
-			MenuItems::Exit,
-			MenuItems::Reboot,
-			MenuItems::Shutdown,
-		]
+	/// An application with every system of `test-systems.xml` holding the games of
+	/// `test-gamelist.xml`.
+	#[cfg(test)]
+	pub(crate) fn with_test_systems() -> Self {
+		let mut all_systems = SystemList::from_file("test-systems.xml".into()).unwrap();
+
+		let gamelist = crate::GameList::from_file("test-gamelist.xml".into())
+			.unwrap()
+			.game
+			.iter()
+			.map(|x| Into::<Game>::into(x.clone()))
+			.collect::<Vec<Game>>();
+
+		for x in &mut all_systems.system {
+			x.games = gamelist.clone();
+			x.refresh();
+		}
+		all_systems.apply_config(&Config::default());
+
+		Self {
+			all_systems: Arc::new(Mutex::new(all_systems)),
+			..Default::default()
+		}
 	}
 
-	pub fn settings_menu(&self) -> Vec<ConfigSettings> {
-		vec![
-			ConfigSettings::SwapConfirm,
-			ConfigSettings::StartFullscreen,
-			ConfigSettings::Theme,
-			ConfigSettings::EnableKeyboard,
-		]
+	/// Where `es_systems.cfg`, the gamelists, collections and themes are.
+	pub fn data_dir(&self) -> &Path {
+		&self.data_dir
 	}
 
-	pub async fn setting_values(&self) -> Vec<String> {
-		let config = self.config.lock().await;
-		vec![
-			serde_json::to_string(&config.swap_confirm).unwrap(),
-			serde_json::to_string(&config.start_fullscreen).unwrap(),
-			serde_json::to_string(&config.theme).unwrap(),
-			serde_json::to_string(&config.enable_keyboard).unwrap(),
-		]
+	/// Connected controllers in connection order, the first being the primary one.
+	pub async fn controllers_menu(&self) -> Vec<String> {
+		let controllers = self.controllers.lock().await;
+
+		if controllers.is_empty() {
+			return vec!["No controllers connected".to_string()];
+		}
+
+		controllers
+			.iter()
+			.enumerate()
+			.map(|(i, x)| {
+				if i == 0 {
+					format!("{} (primary)", x.name)
+				} else {
+					x.name.clone()
+				}
+			})
+			.collect()
 	}
 
-	pub fn settings_types(&self) -> Vec<String> {
-		vec![
-			ConfigSettings::SwapConfirm.type_for(),
-			ConfigSettings::StartFullscreen.type_for(),
-			ConfigSettings::Theme.type_for(),
-			ConfigSettings::EnableKeyboard.type_for(),
-		]
+	/// The configured theme, or the built-in one if it isn't set or no longer installed.
+	pub async fn active_theme(&self) -> ActiveTheme {
+		let Some(id) = self.config.lock().await.theme.clone() else {
+			return ActiveTheme::default();
+		};
+
+		match discover_themes(&themes_dir(&self.data_dir))
+			.into_iter()
+			.find(|x| x.id == id)
+		{
+			Some(theme) => theme.active(),
+			None => {
+				tracing::warn!("theme {} is not installed, using the default theme", id);
+				ActiveTheme::default()
+			}
+		}
 	}
 
-	async fn event_input_cancel(&self) {
-		let mut orientation = self.orientation.lock().await;
+	/// The layout of the active theme for a system, if the theme is an EmulationStation one.
+	pub async fn theme_layout(&self, system: &System) -> Option<ThemeLayout> {
+		let id = self.config.lock().await.theme.clone()?;
+		let theme = discover_themes(&themes_dir(&self.data_dir))
+			.into_iter()
+			.find(|x| x.id == id && x.manifest.format == ThemeFormat::EmulationStation)?;
 
-		if orientation.menu_active {
-			if orientation.menu_item_index.is_some() {
-				orientation.menu_item_index = None;
-			} else {
-				orientation.menu_active = false;
-				orientation.menu_index = None;
-				orientation.menu_item_index = None;
+		let options = EsThemeOptions {
+			system_name: system.name.clone(),
+			system_full_name: system.fullname.clone(),
+			system_theme: system.theme_folder().to_string(),
+			..Default::default()
+		};
+
+		match load_es_theme(&theme.path, &options) {
+			Ok(layout) => Some(layout),
+			Err(e) => {
+				tracing::error!("could not load theme {}: {}", id, e);
+				None
 			}
 		}
 	}
 
-	async fn event_input_ok(&self) {
-		let mut orientation = self.orientation.lock().await;
+	/// Shows a short notification in the frontend.
+	pub fn toast(&self, message: impl Into<String>) {
+		let message = message.into();
+		tracing::info!("{}", message);
 
-		if orientation.menu_active {
-			if let Some(idx) = orientation.menu_index {
-				match MenuItems::from(idx) {
-					MenuItems::Settings => match orientation.menu_item_index {
-						Some(inner_idx) => {
-							let mut config = self.config.lock().await;
-							match ConfigSettings::from(inner_idx) {
-								ConfigSettings::Theme => {}
-								ConfigSettings::EnableKeyboard => {
-									config.enable_keyboard = !config.enable_keyboard
-								}
-								ConfigSettings::StartFullscreen => {
-									config.start_fullscreen = !config.start_fullscreen
-								}
-								ConfigSettings::SwapConfirm => {
-									config.swap_confirm = !config.swap_confirm
-								}
-							}
-						}
-						None => {
-							tracing::debug!("initializing inner menu state");
-							orientation.menu_item_index = Some(0);
-						}
-					},
-					MenuItems::Reboot => {
-						self.config
-							.lock()
-							.await
-							.to_file(&self.config_filename)
-							.unwrap_or_else(|_| {
-								panic!("could not write file: {}", self.config_filename.display())
-							});
-
-						std::process::Command::new("reboot")
-							.status()
-							.expect("could not reboot");
-					}
-					MenuItems::Shutdown => {
-						self.config
-							.lock()
-							.await
-							.to_file(&self.config_filename)
-							.unwrap_or_else(|_| {
-								panic!("could not write file: {}", self.config_filename.display())
-							});
-
-						std::process::Command::new("poweroff")
-							.status()
-							.expect("could not poweroff");
-					}
-					MenuItems::Fullscreen => {
-						if let Some(app_handle) = APP_HANDLE.get() {
-							if let Some(window) = app_handle.get_window("main") {
-								window
-									.set_fullscreen(
-										!window
-											.is_fullscreen()
-											.expect("could not toggle fullscreen"),
-									)
-									.expect("Could not unset fullscreen state");
-							}
-						}
-					}
-					MenuItems::Exit => {
-						self.config
-							.lock()
-							.await
-							.to_file(&self.config_filename)
-							.unwrap_or_else(|_| {
-								panic!("could not write file: {}", self.config_filename.display())
-							});
-
-						std::process::exit(0);
-					}
-				}
+		if let Some(app_handle) = APP_HANDLE.get() {
+			if let Err(e) = app_handle.emit("toast", message) {
+				tracing::warn!("could not send notification: {}", e);
 			}
-		} else {
-			let system = &self.all_systems.lock().await.system[orientation.system_index];
+		}
+	}
 
-			let mut is_fullscreen = false;
+	async fn save_config(&self) -> Result<()> {
+		self.config.lock().await.to_file(&self.config_filename)
+	}
 
-			if let Some(app_handle) = APP_HANDLE.get() {
-				if let Some(window) = app_handle.get_window("main") {
-					is_fullscreen = window
-						.is_fullscreen()
-						.expect("could not get fullscreen state");
+	/// Saves where the player is, so the next start lands in the same place.
+	async fn save_navigation(&self) {
+		let mut navigation = self.navigation.lock().await;
+		let orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+		navigation.remember(&systems, &orientation);
+		drop(systems);
+		drop(orientation);
 
-					if is_fullscreen {
-						window
-							.set_fullscreen(false)
-							.expect("Could not unset fullscreen state");
-					}
+		if let Err(e) = navigation.to_file(&self.state_filename) {
+			tracing::error!(
+				"could not write file: {}: {}",
+				self.state_filename.display(),
+				e
+			);
+		}
+	}
+
+	/// Saves the configuration and position before the application goes away. Errors are logged,
+	/// since there is nothing left to do about them.
+	pub(crate) async fn save_on_exit(&self) {
+		if let Err(e) = self.save_config().await {
+			tracing::error!(
+				"could not write file: {}: {}",
+				self.config_filename.display(),
+				e
+			);
+		}
+
+		self.save_navigation().await;
+	}
+
+	/// Saves the configuration and position, and closes the application.
+	pub async fn quit(&self) {
+		self.save_on_exit().await;
+
+		match APP_HANDLE.get() {
+			Some(app_handle) => app_handle.exit(0),
+			None => std::process::exit(0),
+		}
+	}
+
+	/// What the player has to do before there are games to show.
+	pub async fn setup_status(&self) -> SetupStatus {
+		self.setup.lock().await.clone()
+	}
+
+	/// Checks the setup again after the files it depends on may have changed.
+	async fn refresh_setup(&self) {
+		let setup = check_setup(&self.data_dir, &*self.all_systems.lock().await);
+		*self.setup.lock().await = setup;
+	}
+
+	async fn event_setup_ok(&self) {
+		match self.setup_status().await {
+			SetupStatus::MissingDataDirectory { .. } | SetupStatus::MissingSystems { .. } => {
+				match create_es_root(&self.data_dir) {
+					Ok(()) => self.toast(format!("Created {}", self.data_dir.display())),
+					Err(e) => self.toast(format!(
+						"Could not create {}: {}",
+						self.data_dir.display(),
+						e
+					)),
 				}
+				self.refresh_setup().await;
 			}
+			_ => {}
+		}
+	}
 
-			self.ignore_events.store(true, Ordering::SeqCst);
+	async fn event_input_ok(&self) {
+		let orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
 
-			let game = system.gamelist[orientation.gamelist_index].clone();
+		let Some(system) = systems.system.get(orientation.system_index) else {
+			drop(systems);
+			return self.event_setup_ok().await;
+		};
 
-			let command =
-				system.get_command(game.path.expect("Need a path to the rom in gamelist.xml"));
+		let Some(game) = system.gamelist.get(orientation.gamelist_index) else {
+			return;
+		};
 
-			let args = vec!["-c", &command];
-			let mut child = std::process::Command::new("/bin/sh")
-				.args(args)
-				.spawn()
-				// FIXME: probably should do something better here
-				.expect("Could not boot emulator command");
+		let Some(path) = game.path.clone() else {
+			self.toast("This game has no path in gamelist.xml");
+			return;
+		};
 
-			let s = self.clone();
+		let Some(system) = systems.origin(orientation.system_index, game) else {
+			return;
+		};
 
-			tauri::async_runtime::spawn(async move {
-				let _ = child.wait();
-				s.ignore_events.store(false, Ordering::SeqCst);
+		let is_fullscreen = leave_fullscreen();
+		self.ignore_events.store(true, Ordering::SeqCst);
 
-				if let Some(app_handle) = APP_HANDLE.get() {
-					if let Some(window) = app_handle.get_window("main") {
-						window
-							.set_fullscreen(is_fullscreen)
-							.expect("Could not set fullscreen state");
-					}
+		let command = system.get_command(path.clone(), game.altemulator.as_deref());
+
+		let args = vec!["-c", &command];
+		let mut child = match tokio::process::Command::new("/bin/sh").args(args).spawn() {
+			Ok(child) => child,
+			Err(e) => {
+				tracing::error!("could not run {}: {}", command, e);
+				self.toast(format!("Could not launch {}: {}", system.fullname, e));
+				self.ignore_events.store(false, Ordering::SeqCst);
+				set_fullscreen(is_fullscreen);
+				return;
+			}
+		};
+
+		let sender = self.event_send.clone();
+		let mut exit = LauncherExit {
+			system: system.name.clone(),
+			path,
+			success: false,
+			fullscreen: is_fullscreen,
+		};
+
+		tauri::async_runtime::spawn(async move {
+			exit.success = child.wait().await.is_ok_and(|x| x.success());
+			let _ = sender
+				.send(Event {
+					typ: EventType::LauncherExit(exit),
+				})
+				.await;
+		});
+	}
+
+	async fn event_launcher_exit(&self, exit: LauncherExit) {
+		tracing::info!(
+			"game exited (success: {}): {}",
+			exit.success,
+			exit.path.display()
+		);
+
+		self.ignore_events.store(false, Ordering::SeqCst);
+		// playing counts as activity
+		*self.last_input.lock().await = Instant::now();
+
+		{
+			let config = self.config.lock().await;
+			let mut orientation = self.orientation.lock().await;
+			let mut systems = self.all_systems.lock().await;
+			let now = SystemTime::now();
+
+			keep_selection(&mut orientation, &mut systems, |systems| {
+				if let Some(system) =
+					systems.update_game(&exit.system, &exit.path, |x| x.played(now))
+				{
+					self.save_gamelist(system);
 				}
+
+				systems.refresh_collections(&config);
+				systems.apply_config(&config);
 			});
+
+			self.systems_changed();
 		}
+
+		set_fullscreen(exit.fullscreen);
 	}
 
-	async fn event_input_menu(&self) {
+	async fn event_timer(&self, timer: TimerEvent) {
+		match timer {
+			TimerEvent::HideLetter { generation } => {
+				if self.letter_generation.load(Ordering::SeqCst) == generation {
+					self.orientation.lock().await.letter = None;
+				}
+			}
+			TimerEvent::PartyCountdown { generation } => {
+				if self.countdown_generation.load(Ordering::SeqCst) != generation {
+					return;
+				}
+
+				let mut orientation = self.orientation.lock().await;
+				match orientation.countdown {
+					Some(left) if left > 1 => {
+						orientation.countdown = Some(left - 1);
+						self.schedule(
+							Duration::from_secs(1),
+							TimerEvent::PartyCountdown { generation },
+						);
+					}
+					Some(_) => {
+						orientation.countdown = None;
+						drop(orientation);
+						self.event_input_ok().await;
+						self.save_navigation().await;
+					}
+					None => {}
+				}
+			}
+			TimerEvent::Idle => self.start_screensaver().await,
+			TimerEvent::NextSlide { generation } => self.next_slide(generation).await,
+		}
+	}
+
+	/// Reloads every system when gamelists change on disk, staying on the current system if it is
+	/// still there.
+	async fn event_files_changed(&self, files: Vec<PathBuf>) {
+		tracing::info!("files changed: {:?}", files);
+
+		let systems = match load_es(&self.data_dir) {
+			Ok(systems) => systems,
+			Err(e) => {
+				tracing::error!("could not reload game lists: {}", e);
+				return self.refresh_setup().await;
+			}
+		};
+
+		let config = self.config.lock().await;
 		let mut orientation = self.orientation.lock().await;
-		orientation.menu_active = !orientation.menu_active;
-		if orientation.menu_active {
-			orientation.menu_index = Some(0);
-		} else {
-			orientation.menu_index = None;
-			orientation.menu_item_index = None;
+		let mut all_systems = self.all_systems.lock().await;
+
+		keep_selection(&mut orientation, &mut all_systems, |all_systems| {
+			// virtual systems aren't in the configuration, so they are carried over and the
+			// collections rebuilt
+			let virtual_systems: Vec<System> = all_systems
+				.system
+				.iter()
+				.filter(|x| x.is_virtual)
+				.cloned()
+				.collect();
+
+			*all_systems = systems;
+			for system in virtual_systems.into_iter().rev() {
+				all_systems.set_virtual(system);
+			}
+
+			all_systems.refresh_collections(&config);
+			all_systems.apply_config(&config);
+		});
+		drop(all_systems);
+		drop(orientation);
+		drop(config);
+
+		self.refresh_setup().await;
+		self.systems_changed();
+		self.toast("Game lists reloaded");
+	}
+
+	/// The name of the selected system, or an empty string when there are no systems.
+	pub(crate) async fn current_system_name(&self) -> String {
+		let orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+
+		systems
+			.system
+			.get(orientation.system_index)
+			.map(|x| x.name.clone())
+			.unwrap_or_default()
+	}
+
+	/// Applies settings kept per system after they change, keeping the selected game selected
+	/// when its position moves. Collections are only rebuilt when `collections` is set, as that
+	/// goes through every game.
+	async fn apply_system_settings(&self, collections: bool) {
+		let config = self.config.lock().await;
+		let mut orientation = self.orientation.lock().await;
+		let mut all_systems = self.all_systems.lock().await;
+
+		if keep_selection(&mut orientation, &mut all_systems, |x| {
+			if collections {
+				x.refresh_collections(&config);
+			}
+
+			x.apply_config(&config) || collections
+		}) {
+			self.systems_changed();
+		}
+	}
+
+	/// Marks the system list as changed so it gets pushed to the frontend.
+	fn systems_changed(&self) {
+		self.systems_generation.fetch_add(1, Ordering::SeqCst);
+	}
+
+	fn emit_state<T: Serialize + Clone>(&self, event: &str, data: T) {
+		let update = StateUpdate {
+			seq: self.state_seq.fetch_add(1, Ordering::SeqCst) + 1,
+			data,
+		};
+
+		if let Some(app_handle) = APP_HANDLE.get() {
+			if let Err(e) = app_handle.emit(event, update) {
+				tracing::warn!("could not send {} update: {}", event, e);
+			}
+		}
+	}
+
+	/// Pushes any state that changed since the last call to the frontend, as the `orientation`,
+	/// `setup`, `menu`, `settings`, `controllers` and `systems` events.
+	pub async fn publish(&self) {
+		let orientation = self.orientation.lock().await.clone();
+		let setup = self.setup_status().await;
+		let menu = self.menu_view(&orientation.menu_path).await;
+		let settings = self.config.lock().await.clone();
+		let controllers = self.controllers_menu().await;
+		let systems = self.systems_generation.load(Ordering::SeqCst);
+
+		let mut published = self.published.lock().await;
+
+		if published.orientation.as_ref() != Some(&orientation) {
+			self.emit_state("orientation", &orientation);
+			published.orientation = Some(orientation);
+		}
+
+		if published.setup.as_ref() != Some(&setup) {
+			self.emit_state("setup", &setup);
+			published.setup = Some(setup);
+		}
+
+		if published.menu != menu {
+			self.emit_state("menu", &menu);
+			published.menu = menu;
 		}
+
+		if published.settings != settings {
+			self.emit_state("settings", &settings);
+			published.settings = settings;
+		}
+
+		if published.controllers != controllers {
+			self.emit_state("controllers", &controllers);
+			published.controllers = controllers;
+		}
+
+		if published.systems != systems {
+			self.emit_state("systems", &self.all_systems.lock().await.system);
+			published.systems = systems;
+		}
+	}
+
+	/// Sends `timer` back through the event loop after `delay`.
+	pub fn schedule(&self, delay: Duration, timer: TimerEvent) {
+		let sender = self.event_send.clone();
+		tauri::async_runtime::spawn(async move {
+			tokio::time::sleep(delay).await;
+			let _ = sender
+				.send(Event {
+					typ: EventType::Timer(timer),
+				})
+				.await;
+		});
+	}
+
+	async fn event_input_menu(&self) {
+		self.orientation.lock().await.menu_path = vec![0];
 	}
 
 	async fn event_input_right(&self) {
+		let mut navigation = self.navigation.lock().await;
 		let mut lock = self.orientation.lock().await;
-		if !lock.menu_active {
-			let len = self.all_systems.lock().await.system.len() - 1;
-			if lock.system_index >= len {
-				lock.system_index = 0;
-			} else {
-				lock.system_index += 1;
-			}
+		let systems = self.all_systems.lock().await;
+		navigation.remember(&systems, &lock);
 
-			lock.gamelist_index = 0;
+		let len = systems.system.len();
+		if len == 0 {
+			return;
 		}
+
+		lock.system_index = (lock.system_index + 1) % len;
+
+		lock.gamelist_index = navigation.game_index(&systems, lock.system_index);
 	}
 
 	async fn event_input_left(&self) {
+		let mut navigation = self.navigation.lock().await;
 		let mut lock = self.orientation.lock().await;
-		if !lock.menu_active {
-			let len = self.all_systems.lock().await.system.len() - 1;
-			if lock.system_index == 0 {
-				lock.system_index = len;
-			} else {
-				lock.system_index -= 1;
-			}
+		let systems = self.all_systems.lock().await;
+		navigation.remember(&systems, &lock);
 
-			lock.gamelist_index = 0;
+		let len = systems.system.len();
+		if len == 0 {
+			return;
 		}
+
+		lock.system_index = (lock.system_index + len - 1) % len;
+
+		lock.gamelist_index = navigation.game_index(&systems, lock.system_index);
 	}
 
-	async fn event_input_up(&self) {
+	/// Moves the selected game by `offset`, wrapping around at either end of the list. Does
+	/// nothing while there are no games.
+	async fn move_selection(&self, offset: isize) {
 		let mut lock = self.orientation.lock().await;
-		if lock.menu_active {
-			let len = self.menu().len() - 1;
-			if let Some(index) = lock.menu_index {
-				if let Some(inner_idx) = lock.menu_item_index {
-					if inner_idx == 0 {
-						lock.menu_item_index = Some(self.settings_menu().len() - 1);
-					} else {
-						lock.menu_item_index = Some(inner_idx - 1);
-					}
-				} else if index == 0 {
-					lock.menu_index = Some(len);
-				} else {
-					lock.menu_index = Some(index - 1);
-				}
-			} else {
-				lock.menu_index = Some(0)
-			}
-		} else {
-			let len = self.all_systems.lock().await.system[lock.system_index]
-				.gamelist
-				.len() - 1;
-
-			if lock.gamelist_index == 0 {
-				lock.gamelist_index = len;
-			} else {
-				lock.gamelist_index -= 1;
-			}
+		let len = self
+			.all_systems
+			.lock()
+			.await
+			.gamelist_len(lock.system_index);
+		if len == 0 {
+			return;
 		}
+
+		lock.gamelist_index =
+			(lock.gamelist_index as isize + offset).rem_euclid(len as isize) as usize;
+	}
+
+	async fn event_input_up(&self) {
+		self.move_selection(-1).await;
 	}
 
 	async fn event_input_down(&self) {
+		self.move_selection(1).await;
+	}
+
+	async fn event_input_pageup(&self) {
+		let page_size = self.config.lock().await.page_size;
+		self.move_selection(-(page_size as isize)).await;
+	}
+
+	async fn event_input_pagedown(&self) {
+		let page_size = self.config.lock().await.page_size;
+		self.move_selection(page_size as isize).await;
+	}
+
+	async fn event_input_first(&self) {
+		self.orientation.lock().await.gamelist_index = 0;
+	}
+
+	async fn event_input_last(&self) {
+		let mut lock = self.orientation.lock().await;
+		let len = self
+			.all_systems
+			.lock()
+			.await
+			.gamelist_len(lock.system_index);
+		lock.gamelist_index = len.saturating_sub(1);
+	}
+
+	async fn event_input_letter(&self, forward: bool) {
 		let mut lock = self.orientation.lock().await;
-		if lock.menu_active {
-			let len = self.menu().len() - 1;
-
-			if let Some(index) = lock.menu_index {
-				if let Some(inner_idx) = lock.menu_item_index {
-					if inner_idx == self.settings_menu().len() - 1 {
-						lock.menu_item_index = Some(0);
-					} else {
-						lock.menu_item_index = Some(inner_idx + 1);
+		let systems = self.all_systems.lock().await;
+		let Some(system) = systems.system.get(lock.system_index) else {
+			return;
+		};
+		lock.gamelist_index = system.letter_jump(lock.gamelist_index, forward);
+
+		if !self.config.lock().await.letter_overlay {
+			return;
+		}
+
+		lock.letter = system
+			.gamelist
+			.get(lock.gamelist_index)
+			.map(|x| x.first_letter());
+
+		// hide the overlay a moment after the last jump
+		let generation = self.letter_generation.fetch_add(1, Ordering::SeqCst) + 1;
+		self.schedule(
+			Duration::from_secs(1),
+			TimerEvent::HideLetter { generation },
+		);
+	}
+
+	/// Jumps to a random game, in the current system or in any of them. In party mode the game
+	/// launches after a countdown.
+	async fn event_input_random(&self, anywhere: bool) {
+		let party_mode = self.config.lock().await.party_mode;
+		let mut navigation = self.navigation.lock().await;
+		let mut orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+
+		let current = (orientation.system_index, orientation.gamelist_index);
+		let system = (!anywhere).then_some(orientation.system_index);
+		let Some((system_index, gamelist_index)) =
+			systems.random_game(system, current, &mut rand::rng())
+		else {
+			self.toast("There are no games to pick from");
+			return;
+		};
+
+		navigation.remember(&systems, &orientation);
+		orientation.system_index = system_index;
+		orientation.gamelist_index = gamelist_index;
+
+		if !party_mode {
+			return;
+		}
+
+		orientation.countdown = Some(PARTY_COUNTDOWN);
+		let generation = self.countdown_generation.fetch_add(1, Ordering::SeqCst) + 1;
+		self.schedule(
+			Duration::from_secs(1),
+			TimerEvent::PartyCountdown { generation },
+		);
+	}
+
+	async fn event_input_delete(&self) {
+		let mut orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+		let Some(system) = systems.system.get(orientation.system_index) else {
+			return;
+		};
+
+		let Some(game) = system.gamelist.get(orientation.gamelist_index) else {
+			return;
+		};
+
+		let Some(path) = game.path.clone() else {
+			return;
+		};
+
+		let name = game
+			.name
+			.clone()
+			.unwrap_or_else(|| path.to_string_lossy().to_string());
+
+		orientation.dialog = Some(Dialog::new(
+			format!("Delete {}?", name),
+			DialogAction::DeleteGame {
+				system: game.system.clone().unwrap_or(system.name.clone()),
+				path,
+			},
+		));
+	}
+
+	async fn event_dialog(&self, event: InputEvent) {
+		let mut orientation = self.orientation.lock().await;
+		let Some(dialog) = orientation.dialog.as_mut() else {
+			return;
+		};
+
+		let len = dialog.options.len();
+
+		match event {
+			InputEvent::Up => dialog.selected = (dialog.selected + len - 1) % len,
+			InputEvent::Down => dialog.selected = (dialog.selected + 1) % len,
+			InputEvent::Cancel => orientation.dialog = None,
+			InputEvent::Ok => {
+				let dialog = orientation.dialog.take().unwrap();
+
+				match (dialog.choice(), dialog.action) {
+					(DialogChoice::Cancel, _) => {}
+					(choice, DialogAction::DeleteGame { system, path }) => {
+						self.delete_game(
+							&mut orientation,
+							&system,
+							&path,
+							choice == DialogChoice::Hide,
+						)
+						.await
 					}
-				} else if index == len {
-					lock.menu_index = Some(0);
-				} else {
-					lock.menu_index = Some(index + 1);
 				}
-			} else {
-				lock.menu_index = Some(0)
-			}
-		} else {
-			let len = self.all_systems.lock().await.system[lock.system_index]
-				.gamelist
-				.len() - 1;
-
-			if lock.gamelist_index == len {
-				lock.gamelist_index = 0;
-			} else {
-				lock.gamelist_index += 1;
 			}
+			_ => {}
 		}
 	}
 
-	async fn event_input_pageup(&self) {
-		let mut lock = self.orientation.lock().await;
-		if !lock.menu_active {
-			let len = self.all_systems.lock().await.system[lock.system_index]
-				.gamelist
-				.len() - 1;
-			let mut val = lock.gamelist_index as isize - 10;
-
-			while val < 0 {
-				val = len as isize + val
+	/// Hides the game, or removes its entry from the gamelist. The ROM itself is left alone.
+	async fn delete_game(
+		&self, orientation: &mut Orientation, system: &str, path: &Path, hide: bool,
+	) {
+		let mut systems = self.all_systems.lock().await;
+
+		// hidden or not, the game is gone from every virtual system
+		for other in systems.system.iter_mut().filter(|x| x.is_virtual) {
+			let gone =
+				|x: &Game| x.system.as_deref() == Some(system) && x.path.as_deref() == Some(path);
+			other.games.retain(|x| !gone(x));
+			other.gamelist.retain(|x| !gone(x));
+		}
+
+		let Some(system) = systems
+			.system
+			.iter_mut()
+			.find(|x| !x.is_virtual && x.name == system)
+		else {
+			return;
+		};
+
+		if hide {
+			for game in system
+				.games
+				.iter_mut()
+				.filter(|x| x.path.as_deref() == Some(path))
+			{
+				game.hidden = Some(true);
 			}
+		} else {
+			system.games.retain(|x| x.path.as_deref() != Some(path));
+		}
+
+		system.refresh();
+		self.save_gamelist(system);
+		self.systems_changed();
+
+		orientation.gamelist_index = orientation.gamelist_index.min(
+			systems
+				.gamelist_len(orientation.system_index)
+				.saturating_sub(1),
+		);
+	}
 
-			lock.gamelist_index = val as usize;
+	/// Writes a system's gamelist without the file watcher reloading it.
+	fn save_gamelist(&self, system: &System) {
+		let result = match &system.gamelist_path {
+			Some(path) => self.watcher.write(path, || system.save_gamelist()),
+			None => system.save_gamelist(),
+		};
+
+		if let Err(e) = result {
+			tracing::error!("could not save gamelist for {}: {}", system.name, e);
+			self.toast(format!("Could not save gamelist for {}", system.fullname));
 		}
 	}
 
-	async fn event_input_pagedown(&self) {
-		let mut lock = self.orientation.lock().await;
-		if !lock.menu_active {
-			let len = self.all_systems.lock().await.system[lock.system_index]
-				.gamelist
-				.len() - 1;
+	/// Marks or unmarks the selected game as a favorite in its own system's gamelist.
+	async fn event_input_favorite(&self) {
+		let config = self.config.lock().await;
+		let mut orientation = self.orientation.lock().await;
+		let mut systems = self.all_systems.lock().await;
+
+		let Some(system) = systems.system.get(orientation.system_index) else {
+			return;
+		};
 
-			let mut res = lock.gamelist_index + 10;
+		let Some(game) = system.gamelist.get(orientation.gamelist_index) else {
+			return;
+		};
 
-			while res >= len {
-				res = res - len;
+		let Some(path) = game.path.clone() else {
+			return;
+		};
+
+		let origin = game.system.clone().unwrap_or(system.name.clone());
+		let favorite = !game.favorite.unwrap_or_default();
+		let name = game
+			.name
+			.clone()
+			.unwrap_or_else(|| path.to_string_lossy().to_string());
+
+		keep_selection(&mut orientation, &mut systems, |systems| {
+			// ES-DE leaves the tag out rather than writing false
+			if let Some(system) =
+				systems.update_game(&origin, &path, |x| x.favorite = favorite.then_some(true))
+			{
+				self.save_gamelist(system);
 			}
 
-			lock.gamelist_index = res;
+			systems.refresh_collections(&config);
+			systems.apply_config(&config);
+		});
+
+		self.systems_changed();
+		self.toast(if favorite {
+			format!("Added {} to Favorites", name)
+		} else {
+			format!("Removed {} from Favorites", name)
+		});
+	}
+
+	/// Adds the selected game to the custom collection called `name`, or removes it if it is in
+	/// it already. With `create`, a missing collection is made and the game is only ever added.
+	pub(crate) async fn update_collection(&self, name: &str, create: bool) {
+		// the name becomes part of a filename
+		let name = name.replace(['/', '\\'], "-");
+
+		let config = self.config.lock().await;
+		let mut orientation = self.orientation.lock().await;
+		let mut systems = self.all_systems.lock().await;
+
+		let Some((rom, game)) = selected_rom(&orientation, &systems) else {
+			return;
+		};
+
+		if create && !systems.custom.iter().any(|x| x.name == name) {
+			systems.custom.push(CustomCollection::new(
+				&collections_dir(&self.data_dir),
+				&name,
+			));
+			systems.custom.sort_by(|a, b| a.name.cmp(&b.name));
 		}
+
+		let Some(collection) = systems.custom.iter_mut().find(|x| x.name == name) else {
+			return;
+		};
+
+		let added = collection.add(&rom);
+		if !added && !create {
+			collection.remove(&rom);
+		}
+
+		if let Err(e) = self
+			.watcher
+			.write(&collection.filename, || collection.to_file())
+		{
+			tracing::error!(
+				"could not save collection {}: {}",
+				collection.filename.display(),
+				e
+			);
+			self.toast(format!("Could not save collection {}", name));
+		}
+
+		self.toast(match (added, create) {
+			(true, _) => format!("Added {} to {}", game, name),
+			(false, true) => format!("{} is already in {}", game, name),
+			(false, false) => format!("Removed {} from {}", game, name),
+		});
+
+		keep_selection(&mut orientation, &mut systems, |systems| {
+			systems.refresh_collections(&config);
+			systems.apply_config(&config);
+		});
+
+		self.systems_changed();
 	}
 
 	pub async fn event_loop(&self) {
-		loop {
-			let event = self.next_event().await;
+		while let Some(event) = self.next_event().await {
+			self.handle_event(event).await;
+			self.publish().await;
+		}
+
+		tracing::error!("all event sources have closed, stopping the event loop");
+	}
+
+	/// Routes an event to its handler.
+	pub async fn handle_event(&self, event: Event) {
+		match event.typ {
+			EventType::Input(e) => self.event_input(e).await,
+			EventType::Controller(e) => self.event_controller(e).await,
+			EventType::LauncherExit(e) => self.event_launcher_exit(e).await,
+			EventType::Timer(e) => self.event_timer(e).await,
+			EventType::FilesChanged(e) => self.event_files_changed(e).await,
+		}
+	}
+
+	async fn event_input(&self, event: InputEvent) {
+		// input belongs to the emulator while a game is running
+		if self.ignore_events.load(Ordering::SeqCst) {
+			return;
+		}
+
+		tracing::debug!("input event: {:?}", event);
 
-			if event.is_none() {
-				continue;
+		*self.last_input.lock().await = Instant::now();
+		if self.orientation.lock().await.screensaver.is_some() {
+			return self.event_screensaver(event).await;
+		}
+
+		let restricted = self.config.lock().await.ui_mode.is_restricted();
+
+		if event == InputEvent::Quit && !restricted {
+			return self.quit().await;
+		}
+
+		let (dialog, menu, keyboard, unlock, view) = {
+			let mut orientation = self.orientation.lock().await;
+			if orientation.countdown.take().is_some() {
+				self.toast("Launch cancelled");
+				return;
 			}
 
-			if self.ignore_events.load(Ordering::SeqCst) {
-				continue;
+			(
+				orientation.dialog.is_some(),
+				orientation.menu_active(),
+				orientation.keyboard.is_some(),
+				orientation.unlock.is_some(),
+				orientation.views.last().cloned(),
+			)
+		};
+
+		if unlock {
+			return self.event_unlock(event).await;
+		}
+
+		if dialog {
+			return self.event_dialog(event).await;
+		}
+
+		if keyboard {
+			return self.event_keyboard(event).await;
+		}
+
+		if menu {
+			return self.event_menu(event).await;
+		}
+
+		match view {
+			Some(View::Details { .. }) => return self.event_details(event).await,
+			Some(View::Editor { .. }) => return self.event_editor(event).await,
+			Some(View::GameList) | None => {}
+		}
+
+		match event {
+			InputEvent::Cancel => {}
+			InputEvent::Ok => {
+				self.event_input_ok().await;
+				// a game can take the whole machine down with it
+				self.save_navigation().await;
 			}
+			InputEvent::Menu => self.event_input_menu().await,
+			InputEvent::Right => self.event_input_right().await,
+			InputEvent::Left => self.event_input_left().await,
+			InputEvent::Up => self.event_input_up().await,
+			InputEvent::Down => self.event_input_down().await,
+			InputEvent::PageUp => self.event_input_pageup().await,
+			InputEvent::PageDown => self.event_input_pagedown().await,
+			InputEvent::First => self.event_input_first().await,
+			InputEvent::Last => self.event_input_last().await,
+			// games can't be changed outside of full mode
+			InputEvent::Delete | InputEvent::ToggleFavorite if restricted => {}
+			InputEvent::Delete => self.event_input_delete().await,
+			InputEvent::NextLetter => self.event_input_letter(true).await,
+			InputEvent::PreviousLetter => self.event_input_letter(false).await,
+			InputEvent::Search => self.open_keyboard(KeyboardPurpose::Search).await,
+			InputEvent::ToggleFavorite => self.event_input_favorite().await,
+			InputEvent::Details => self.open_details().await,
+			InputEvent::RandomGame => self.event_input_random(false).await,
+			InputEvent::RandomGameAnywhere => self.event_input_random(true).await,
+			InputEvent::Quit => {}
+		}
+	}
 
-			match event.unwrap().typ {
-				EventType::Input(e) => {
-					tracing::debug!("input event: {:?}", e);
-					match e {
-						InputEvent::Cancel => self.event_input_cancel().await,
-						InputEvent::Ok => self.event_input_ok().await,
-						InputEvent::Menu => self.event_input_menu().await,
-						InputEvent::Right => self.event_input_right().await,
-						InputEvent::Left => self.event_input_left().await,
-						InputEvent::Up => self.event_input_up().await,
-						InputEvent::Down => self.event_input_down().await,
-						InputEvent::PageUp => self.event_input_pageup().await,
-						InputEvent::PageDown => self.event_input_pagedown().await,
-						_ => {}
-					}
+	/// Input while the unlock PIN is being entered. Every button counts towards it, so a wrong
+	/// PIN is only turned down once it is as long as the right one.
+	async fn event_unlock(&self, event: InputEvent) {
+		let mut config = self.config.lock().await;
+		let mut orientation = self.orientation.lock().await;
+		let Some(entered) = orientation.unlock.as_mut() else {
+			return;
+		};
+
+		entered.push(event);
+		if entered.len() < config.unlock_pin.len() {
+			return;
+		}
+
+		let unlocked = *entered == config.unlock_pin;
+		orientation.unlock = None;
+		if !unlocked {
+			self.toast("Wrong PIN");
+			return;
+		}
+
+		config.ui_mode = UiMode::Full;
+		drop(orientation);
+		drop(config);
+
+		self.toast("Full mode unlocked");
+		if let Err(e) = self.save_config().await {
+			tracing::error!(
+				"could not write file: {}: {}",
+				self.config_filename.display(),
+				e
+			);
+		}
+		self.apply_system_settings(true).await;
+	}
+
+	async fn event_controller(&self, event: ControllerEvent) {
+		let mut controllers = self.controllers.lock().await;
+
+		match event {
+			ControllerEvent::Connected { id, name } => {
+				if !controllers.iter().any(|x| x.id == id) {
+					self.toast(format!("Controller connected: {}", name));
+					controllers.push(Controller { id, name });
+				}
+			}
+			ControllerEvent::Disconnected { id } => {
+				if let Some(pos) = controllers.iter().position(|x| x.id == id) {
+					let controller = controllers.remove(pos);
+					self.toast(format!("Controller disconnected: {}", controller.name));
 				}
 			}
 		}
 	}
 
+	/// Waits for the next event from any source. Returns `None` once every source has closed.
 	pub async fn next_event(&self) -> Option<Event> {
-		let mut chan = self.input_recv.lock().await;
-		if let Ok(item) = chan.try_recv() {
-			tracing::debug!("received event {:?}", item);
+		let idle = self.idle_deadline().await;
+		let sleep = tokio::time::sleep_until(idle.unwrap_or_else(Instant::now).into());
+		let mut input = self.input_recv.lock().await;
+		let mut controllers = self.controller_recv.lock().await;
+		let mut events = self.event_recv.lock().await;
 
-			Some(Event {
-				typ: EventType::Input(item),
-			})
-		} else {
-			tokio::time::sleep(Duration::from_millis(50)).await;
-			None
+		let typ = tokio::select! {
+			Some(e) = controllers.recv() => EventType::Controller(e),
+			Some(e) = input.recv() => EventType::Input(e),
+			Some(e) = events.recv() => return Some(e),
+			_ = sleep, if idle.is_some() => EventType::Timer(TimerEvent::Idle),
+			else => return None,
+		};
+
+		Some(Event { typ })
+	}
+}
+
+/// Takes the window out of fullscreen for an emulator, returning whether it was fullscreen.
+fn leave_fullscreen() -> bool {
+	let Some(window) = APP_HANDLE.get().and_then(|x| x.get_window("main")) else {
+		return false;
+	};
+
+	match window.is_fullscreen() {
+		Ok(false) => false,
+		Ok(true) => {
+			set_fullscreen(false);
+			true
+		}
+		Err(e) => {
+			tracing::error!("could not get the fullscreen state: {}", e);
+			false
+		}
+	}
+}
+
+fn set_fullscreen(fullscreen: bool) {
+	if let Some(window) = APP_HANDLE.get().and_then(|x| x.get_window("main")) {
+		if let Err(e) = window.set_fullscreen(fullscreen) {
+			tracing::error!("could not set the fullscreen state: {}", e);
+		}
+	}
+}
+
+/// The absolute path of the selected game's ROM, with the game's name.
+pub(crate) fn selected_rom(
+	orientation: &Orientation, systems: &SystemList,
+) -> Option<(PathBuf, String)> {
+	let game = systems
+		.system
+		.get(orientation.system_index)?
+		.gamelist
+		.get(orientation.gamelist_index)?;
+	let rom = systems
+		.origin(orientation.system_index, game)?
+		.rom_path(game)?;
+	let name = game
+		.name
+		.clone()
+		.unwrap_or_else(|| rom.to_string_lossy().to_string());
+
+	Some((rom, name))
+}
+
+/// Runs `f` on the system list, then finds the selected system and game again by name and path,
+/// as their positions may have moved. A game that is gone leaves the selection where it was, as
+/// far as the list still reaches.
+fn keep_selection<T>(
+	orientation: &mut Orientation, systems: &mut SystemList, f: impl FnOnce(&mut SystemList) -> T,
+) -> T {
+	let system = systems
+		.system
+		.get(orientation.system_index)
+		.map(|x| (x.name.clone(), x.is_virtual));
+	let game = systems
+		.system
+		.get(orientation.system_index)
+		.and_then(|x| x.gamelist.get(orientation.gamelist_index))
+		.filter(|x| x.path.is_some())
+		.map(|x| (x.system.clone(), x.path.clone()));
+
+	let result = f(systems);
+
+	match system.and_then(|(name, is_virtual)| {
+		systems
+			.system
+			.iter()
+			.position(|x| x.name == name && x.is_virtual == is_virtual)
+	}) {
+		Some(index) => orientation.system_index = index,
+		None => {
+			orientation.system_index = 0;
+			orientation.gamelist_index = 0;
+		}
+	}
+
+	let gamelist = systems
+		.system
+		.get(orientation.system_index)
+		.map(|x| x.gamelist.as_slice())
+		.unwrap_or_default();
+
+	orientation.gamelist_index = game
+		.and_then(|(system, path)| {
+			gamelist
+				.iter()
+				.position(|x| x.system == system && x.path == path)
+		})
+		.unwrap_or(
+			orientation
+				.gamelist_index
+				.min(gamelist.len().saturating_sub(1)),
+		);
+
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		App, Cli, ControllerEvent, Event, EventType, InputBackend, InputEvent, Orientation,
+		ScreensaverType, ScriptedBackend, SystemList, TimerEvent, UiMode, View,
+	};
+
+	#[tokio::test]
+	async fn test_scripted_input() {
+		let app = App::with_test_systems();
+
+		Box::new(ScriptedBackend::presses([
+			InputEvent::Down,
+			InputEvent::Down,
+			InputEvent::Right,
+			InputEvent::Down,
+		]))
+		.start(app.input_send.clone())
+		.unwrap();
+
+		for _ in 0..4 {
+			let event = app.next_event().await.unwrap();
+			app.handle_event(event).await;
+		}
+
+		let orientation = app.orientation.lock().await;
+		assert_eq!(orientation.system_index, 1);
+		assert_eq!(orientation.gamelist_index, 1);
+	}
+
+	#[tokio::test]
+	async fn test_controller_hotplug() {
+		let app = App::with_test_systems();
+		assert_eq!(app.controllers_menu().await, ["No controllers connected"]);
+
+		for event in [
+			ControllerEvent::Connected {
+				id: 0,
+				name: "8BitDo SN30 Pro".into(),
+			},
+			ControllerEvent::Connected {
+				id: 1,
+				name: "Xbox Controller".into(),
+			},
+			// reported again by the backend; not a new controller
+			ControllerEvent::Connected {
+				id: 1,
+				name: "Xbox Controller".into(),
+			},
+		] {
+			app.controller_send.send(event).await.unwrap();
+			let event = app.next_event().await.unwrap();
+			app.handle_event(event).await;
+		}
+
+		assert_eq!(
+			app.controllers_menu().await,
+			["8BitDo SN30 Pro (primary)", "Xbox Controller"]
+		);
+
+		// the next one in connection order becomes the primary controller
+		app.controller_send
+			.send(ControllerEvent::Disconnected { id: 0 })
+			.await
+			.unwrap();
+		let event = app.next_event().await.unwrap();
+		app.handle_event(event).await;
+		assert_eq!(app.controllers_menu().await, ["Xbox Controller (primary)"]);
+	}
+
+	#[tokio::test]
+	async fn test_menu_navigation() {
+		let app = App::with_test_systems();
+
+		let mut events = vec![InputEvent::Menu, InputEvent::Ok];
+		events.extend(std::iter::repeat_n(InputEvent::Down, 5));
+		events.push(InputEvent::Ok);
+
+		for event in events {
+			app.event_input(event).await;
+		}
+
+		assert_eq!(app.orientation.lock().await.menu_path, vec![0, 5]);
+		assert!(!app.config.lock().await.letter_overlay);
+
+		let view = app.menu_view(&[0, 5]).await.unwrap();
+		assert_eq!(view.title, "Settings");
+
+		// ranges stop at their bounds
+		for event in [InputEvent::Down, InputEvent::Right, InputEvent::Right] {
+			app.event_input(event).await;
 		}
+		assert_eq!(app.config.lock().await.volume, 90);
+
+		app.config.lock().await.volume = 100;
+		app.event_input(InputEvent::Right).await;
+		assert_eq!(app.config.lock().await.volume, 100);
+
+		app.event_input(InputEvent::Cancel).await;
+		app.event_input(InputEvent::Cancel).await;
+		assert!(!app.orientation.lock().await.menu_active());
+	}
+
+	#[tokio::test]
+	async fn test_empty_library() {
+		let app = App::with_test_systems();
+		*app.all_systems.lock().await = SystemList::default();
+
+		for event in [
+			InputEvent::Up,
+			InputEvent::Down,
+			InputEvent::Left,
+			InputEvent::Right,
+			InputEvent::PageUp,
+			InputEvent::PageDown,
+			InputEvent::First,
+			InputEvent::Last,
+			InputEvent::NextLetter,
+			InputEvent::Delete,
+		] {
+			app.event_input(event).await;
+		}
+
+		assert_eq!(*app.orientation.lock().await, Orientation::default());
+	}
+
+	#[test]
+	fn test_missing_config_file() {
+		let cli = Cli {
+			config: Some(std::env::temp_dir().join("rawrcade-no-such-config.json")),
+			data_dir: Some(std::env::temp_dir().join("rawrcade-no-such-data")),
+			..Default::default()
+		};
+		assert!(App::new(&cli).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_kiosk_mode() {
+		let app = App::with_test_systems();
+		{
+			let mut config = app.config.lock().await;
+			config.ui_mode = UiMode::Kiosk;
+			config.unlock_pin = vec![InputEvent::Up, InputEvent::Ok];
+		}
+
+		let labels: Vec<String> = app.menu_tree().await.into_iter().map(|x| x.label).collect();
+		assert_eq!(labels, vec!["Controllers", "Unlock Full Mode"]);
+
+		// no deleting games
+		app.event_input(InputEvent::Delete).await;
+		assert_eq!(app.orientation.lock().await.dialog, None);
+
+		// nor editing them, even with the editor left open from before
+		app.orientation.lock().await.views = vec![
+			View::Details {
+				selected: 0,
+				scroll: 0,
+			},
+			View::Editor { selected: 5 },
+		];
+		app.event_input(InputEvent::Ok).await;
+		assert!(matches!(
+			app.orientation.lock().await.views[..],
+			[View::Details { .. }]
+		));
+		assert_eq!(
+			app.all_systems.lock().await.system[0].gamelist[0].favorite,
+			None
+		);
+		app.orientation.lock().await.views.clear();
+
+		app.event_input(InputEvent::Menu).await;
+		app.event_input(InputEvent::Down).await;
+		app.event_input(InputEvent::Ok).await;
+		assert_eq!(app.orientation.lock().await.unlock, Some(Vec::new()));
+
+		app.event_input(InputEvent::Up).await;
+		app.event_input(InputEvent::Cancel).await;
+		assert_eq!(app.orientation.lock().await.unlock, None);
+		assert_eq!(app.config.lock().await.ui_mode, UiMode::Kiosk);
+	}
+
+	#[tokio::test]
+	async fn test_screensaver() {
+		let app = App::with_test_systems();
+		app.config.lock().await.screensaver = ScreensaverType::Slideshow;
+
+		app.handle_event(Event {
+			typ: EventType::Timer(TimerEvent::Idle),
+		})
+		.await;
+		let slide = app
+			.orientation
+			.lock()
+			.await
+			.screensaver
+			.clone()
+			.unwrap()
+			.slide;
+		assert!(slide.unwrap().image.unwrap().is_absolute());
+		assert_eq!(app.idle_deadline().await, None);
+
+		// waking up doesn't move the selection
+		app.event_input(InputEvent::Down).await;
+		let orientation = app.orientation.lock().await;
+		assert_eq!(orientation.screensaver, None);
+		assert_eq!(orientation.gamelist_index, 0);
 	}
 }
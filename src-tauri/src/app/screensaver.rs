@@ -0,0 +1,173 @@
+use super::{App, InputEvent, TimerEvent};
+use crate::{Game, ScreensaverType, SystemList};
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::{
+	path::PathBuf,
+	sync::atomic::Ordering,
+	time::{Duration, Instant},
+};
+
+// how long each game is shown for
+const SLIDE_SECONDS: u64 = 10;
+const VIDEO_SECONDS: u64 = 30;
+
+/// The screensaver, started by [TimerEvent::Idle] and stopped by any input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Screensaver {
+	pub kind: ScreensaverType,
+	/// The game being shown; always `None` for [ScreensaverType::Dim].
+	pub slide: Option<Slide>,
+}
+
+/// A game shown by the screensaver, with its media resolved to absolute paths.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Slide {
+	pub system: String,
+	pub name: String,
+	pub image: Option<PathBuf>,
+	pub video: Option<PathBuf>,
+	pub system_index: usize,
+	pub gamelist_index: usize,
+}
+
+impl Slide {
+	/// A random visible game of a real system that has the media `kind` shows.
+	pub fn random(systems: &SystemList, kind: ScreensaverType) -> Option<Self> {
+		// gamelists often carry empty tags for missing media
+		let media = |game: &Game| {
+			match kind {
+				ScreensaverType::Dim => None,
+				ScreensaverType::Slideshow => game.image.clone(),
+				ScreensaverType::Video => game.video.clone(),
+			}
+			.filter(|x| !x.as_os_str().is_empty())
+		};
+
+		let games: Vec<(usize, usize)> = systems
+			.system
+			.iter()
+			.enumerate()
+			.filter(|(_, x)| !x.is_virtual)
+			.flat_map(|(index, system)| {
+				system
+					.gamelist
+					.iter()
+					.enumerate()
+					.filter(|(_, game)| media(game).is_some())
+					.map(move |(game, _)| (index, game))
+			})
+			.collect();
+
+		let (system_index, gamelist_index) = *games.choose(&mut rand::rng())?;
+		let system = &systems.system[system_index];
+		let game = &system.gamelist[gamelist_index];
+		let resolve = |path: &Option<PathBuf>| {
+			path.as_ref()
+				.filter(|x| !x.as_os_str().is_empty())
+				.and_then(|x| system.resolve(x))
+		};
+
+		Some(Self {
+			system: system.fullname.clone(),
+			name: game.name.clone().unwrap_or_default(),
+			image: resolve(&game.image),
+			video: resolve(&game.video),
+			system_index,
+			gamelist_index,
+		})
+	}
+}
+
+impl App {
+	/// When the screensaver starts if nothing is pressed, or `None` while it can't start: it is
+	/// turned off, already running or a game is being played.
+	pub(crate) async fn idle_deadline(&self) -> Option<Instant> {
+		let delay = self.config.lock().await.screensaver_delay;
+		if delay == 0
+			|| self.ignore_events.load(Ordering::SeqCst)
+			|| self.orientation.lock().await.screensaver.is_some()
+		{
+			return None;
+		}
+
+		Some(*self.last_input.lock().await + Duration::from_secs(delay * 60))
+	}
+
+	pub(crate) async fn start_screensaver(&self) {
+		let kind = self.config.lock().await.screensaver;
+		self.orientation.lock().await.screensaver = Some(Screensaver { kind, slide: None });
+
+		if kind != ScreensaverType::Dim {
+			self.next_slide(self.slide_generation.load(Ordering::SeqCst))
+				.await;
+		}
+	}
+
+	/// Shows another game, unless the screensaver stopped or was restarted since `generation`.
+	/// Without any games with media it stays dark.
+	pub(crate) async fn next_slide(&self, generation: usize) {
+		let mut orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+		if self.slide_generation.load(Ordering::SeqCst) != generation {
+			return;
+		}
+
+		let Some(screensaver) = orientation.screensaver.as_mut() else {
+			return;
+		};
+
+		screensaver.slide = Slide::random(&systems, screensaver.kind);
+		let seconds = match screensaver.kind {
+			ScreensaverType::Video => VIDEO_SECONDS,
+			_ => SLIDE_SECONDS,
+		};
+
+		if screensaver.slide.is_some() {
+			self.schedule(
+				Duration::from_secs(seconds),
+				TimerEvent::NextSlide { generation },
+			);
+		}
+	}
+
+	/// Input while the screensaver runs. Any button wakes the frontend up; with
+	/// [crate::Config::screensaver_launch], Ok also launches the game being shown.
+	pub(crate) async fn event_screensaver(&self, event: InputEvent) {
+		let launch = self.config.lock().await.screensaver_launch;
+		let mut navigation = self.navigation.lock().await;
+		let mut orientation = self.orientation.lock().await;
+		let systems = self.all_systems.lock().await;
+
+		self.slide_generation.fetch_add(1, Ordering::SeqCst);
+		let Some(screensaver) = orientation.screensaver.take() else {
+			return;
+		};
+
+		let Some(slide) = screensaver
+			.slide
+			.filter(|_| launch && event == InputEvent::Ok)
+		else {
+			return;
+		};
+
+		// the library may have been reloaded since the slide was picked
+		let game = systems
+			.system
+			.get(slide.system_index)
+			.and_then(|x| x.gamelist.get(slide.gamelist_index));
+		if game.is_none_or(|x| x.name.clone().unwrap_or_default() != slide.name) {
+			return;
+		}
+
+		navigation.remember(&systems, &orientation);
+		orientation.system_index = slide.system_index;
+		orientation.gamelist_index = slide.gamelist_index;
+		drop(systems);
+		drop(orientation);
+		drop(navigation);
+
+		self.event_input_ok().await;
+		self.save_navigation().await;
+	}
+}
@@ -0,0 +1,183 @@
+use super::{App, InputEvent};
+use crate::{SEARCH_SYSTEM, System, search};
+use serde::{Deserialize, Serialize};
+
+const ROWS: [&str; 4] = ["1234567890", "QWERTYUIOP", "ASDFGHJKL'", "ZXCVBNM-.&"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Key {
+	Char {
+		value: char,
+	},
+	Space,
+	/// Switches between lower and upper case.
+	Shift,
+	Backspace,
+	Clear,
+	/// Finishes typing, which runs the search or whatever the keyboard was opened for.
+	Done,
+}
+
+/// What the text typed on the keyboard is for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyboardPurpose {
+	#[default]
+	Search,
+	/// The name of a custom collection to create with the selected game in it.
+	NewCollection,
+	/// The field selected in the metadata editor.
+	EditMetadata,
+}
+
+/// The keyboard shown for typing, like when searching. It is driven by the same input as
+/// everything else: the directions move between keys and Ok presses the selected one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnScreenKeyboard {
+	pub purpose: KeyboardPurpose,
+	pub text: String,
+	/// Letters are typed in upper case.
+	pub shift: bool,
+	pub rows: Vec<Vec<Key>>,
+	pub row: usize,
+	pub column: usize,
+}
+
+impl Default for OnScreenKeyboard {
+	fn default() -> Self {
+		Self::new(KeyboardPurpose::default())
+	}
+}
+
+impl OnScreenKeyboard {
+	pub fn new(purpose: KeyboardPurpose) -> Self {
+		let mut rows: Vec<Vec<Key>> = ROWS
+			.iter()
+			.map(|row| row.chars().map(|value| Key::Char { value }).collect())
+			.collect();
+		rows.push(vec![
+			Key::Space,
+			Key::Shift,
+			Key::Backspace,
+			Key::Clear,
+			Key::Done,
+		]);
+
+		Self {
+			purpose,
+			text: String::new(),
+			shift: false,
+			rows,
+			// start on Q
+			row: 1,
+			column: 0,
+		}
+	}
+
+	pub fn selected(&self) -> Option<&Key> {
+		self.rows.get(self.row)?.get(self.column)
+	}
+
+	/// Moves the selection with Up, Down, Left and Right, wrapping around at the edges. Rows
+	/// have different lengths, so moving between them keeps the column where possible.
+	pub fn navigate(&mut self, event: &InputEvent) {
+		let rows = self.rows.len();
+		let len = |row: usize| self.rows[row].len();
+
+		match event {
+			InputEvent::Up => self.row = (self.row + rows - 1) % rows,
+			InputEvent::Down => self.row = (self.row + 1) % rows,
+			InputEvent::Left => self.column = (self.column + len(self.row) - 1) % len(self.row),
+			InputEvent::Right => self.column = (self.column + 1) % len(self.row),
+			_ => {}
+		}
+
+		self.column = self.column.min(len(self.row) - 1);
+	}
+
+	/// Types the selected key. Returns true when it was [Key::Done].
+	pub fn press(&mut self) -> bool {
+		match self.selected().cloned() {
+			Some(Key::Char { value }) => self.text.push(match self.shift {
+				true => value.to_ascii_uppercase(),
+				false => value.to_ascii_lowercase(),
+			}),
+			Some(Key::Space) => self.text.push(' '),
+			Some(Key::Shift) => self.shift = !self.shift,
+			Some(Key::Backspace) => {
+				self.text.pop();
+			}
+			Some(Key::Clear) => self.text.clear(),
+			Some(Key::Done) => return true,
+			None => {}
+		}
+
+		false
+	}
+}
+
+impl App {
+	/// Opens the keyboard over everything else.
+	pub(crate) async fn open_keyboard(&self, purpose: KeyboardPurpose) {
+		let mut orientation = self.orientation.lock().await;
+		orientation.menu_path.clear();
+		orientation.keyboard = Some(OnScreenKeyboard::new(purpose));
+	}
+
+	/// Input while the keyboard is open. Cancel closes it and Delete erases a letter.
+	pub(crate) async fn event_keyboard(&self, event: InputEvent) {
+		let mut orientation = self.orientation.lock().await;
+		let Some(keyboard) = orientation.keyboard.as_mut() else {
+			return;
+		};
+
+		match event {
+			InputEvent::Up | InputEvent::Down | InputEvent::Left | InputEvent::Right => {
+				keyboard.navigate(&event)
+			}
+			InputEvent::Delete => {
+				keyboard.text.pop();
+			}
+			InputEvent::Cancel | InputEvent::Search => orientation.keyboard = None,
+			InputEvent::Ok => {
+				if !keyboard.press() {
+					return;
+				}
+
+				// clearing a field is an edit, but there's nothing to search for
+				let text = keyboard.text.trim().to_string();
+				let purpose = keyboard.purpose;
+				if text.is_empty() && purpose != KeyboardPurpose::EditMetadata {
+					return;
+				}
+
+				orientation.keyboard = None;
+				drop(orientation);
+
+				match purpose {
+					KeyboardPurpose::Search => self.show_search(&text).await,
+					KeyboardPurpose::NewCollection => self.update_collection(&text, true).await,
+					KeyboardPurpose::EditMetadata => self.edit_text(&text).await,
+				}
+			}
+			_ => {}
+		}
+	}
+
+	/// Shows the games matching `query` in the search system.
+	async fn show_search(&self, query: &str) {
+		let mut orientation = self.orientation.lock().await;
+		let mut systems = self.all_systems.lock().await;
+		let results = search(&systems, query);
+		if results.is_empty() {
+			self.toast(format!("Nothing found for \"{}\"", query));
+			return;
+		}
+
+		let system = System::new_virtual(SEARCH_SYSTEM, &format!("Search: {}", query), results);
+		orientation.system_index = systems.set_virtual(system);
+		orientation.gamelist_index = 0;
+		self.systems_changed();
+	}
+}
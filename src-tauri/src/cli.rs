@@ -0,0 +1,58 @@
+use crate::LogLevel;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// RAWRcade, an emulator frontend driven by a controller. The options only last for this run;
+/// none of them are saved to the configuration.
+#[derive(Debug, Clone, Default, PartialEq, Parser)]
+#[command(version, about, long_about = None)]
+pub struct Cli {
+	/// The configuration file to use instead of the one in the user's config directory.
+	#[arg(long, value_name = "FILE")]
+	pub config: Option<PathBuf>,
+	/// The directory with es_systems.cfg, gamelists, collections and themes, instead of
+	/// ~/.rawrcade.
+	#[arg(long, value_name = "DIR")]
+	pub data_dir: Option<PathBuf>,
+	/// Start in a window, whatever the Start Fullscreen setting is.
+	#[arg(long)]
+	pub windowed: bool,
+	/// How much to log, instead of the Log Level setting.
+	#[arg(long, value_enum, value_name = "LEVEL")]
+	pub log_level: Option<LogLevel>,
+	/// Start on this system instead of the one last played.
+	#[arg(long, value_name = "NAME")]
+	pub system: Option<String>,
+	/// Print the systems that have games, then exit.
+	#[arg(long)]
+	pub list_systems: bool,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_cli() {
+		let cli = Cli::try_parse_from([
+			"rawrcade",
+			"--data-dir",
+			"/tmp/rawrcade",
+			"--windowed",
+			"--log-level",
+			"warn",
+			"--system",
+			"snes",
+		])
+		.unwrap();
+
+		assert_eq!(cli.data_dir, Some("/tmp/rawrcade".into()));
+		assert!(cli.windowed);
+		assert_eq!(cli.log_level, Some(LogLevel::Warn));
+		assert_eq!(cli.system.as_deref(), Some("snes"));
+		assert!(!cli.list_systems);
+
+		assert_eq!(Cli::try_parse_from(["rawrcade"]).unwrap(), Cli::default());
+		assert!(Cli::try_parse_from(["rawrcade", "--log-level", "loud"]).is_err());
+	}
+}
@@ -0,0 +1,378 @@
+use crate::{Config, Game, SEARCH_SYSTEM, SortMode, System, SystemList, UiMode};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
+/// The name of the collection of favorite games.
+pub const FAVORITES_SYSTEM: &str = "favorites";
+
+/// Custom collection files are named `custom-<name>.cfg`, and their systems `custom-<name>`.
+pub const CUSTOM_COLLECTION_PREFIX: &str = "custom-";
+
+// ES-DE writes ROM paths in collection files relative to its ROM directory, behind this
+const ROMPATH: &str = "%ROMPATH%";
+
+// how many games the collections ordered by time or count keep
+const COLLECTION_LIMIT: usize = 50;
+
+/// Collections generated from the games of every system, which can each be turned on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoCollection {
+	/// Most recently played first, by `lastplayed`.
+	RecentlyPlayed,
+	/// By `playcount`, leaving out games never played.
+	MostPlayed,
+	AllGames,
+	/// Newest ROM files first.
+	LastAdded,
+}
+
+impl AutoCollection {
+	pub fn all() -> Vec<Self> {
+		vec![
+			Self::RecentlyPlayed,
+			Self::MostPlayed,
+			Self::AllGames,
+			Self::LastAdded,
+		]
+	}
+
+	/// The name of the collection's system.
+	pub fn system_name(&self) -> &'static str {
+		match self {
+			Self::RecentlyPlayed => "recent",
+			Self::MostPlayed => "mostplayed",
+			Self::AllGames => "all",
+			Self::LastAdded => "lastadded",
+		}
+	}
+
+	/// The collection's games, in the collection's order.
+	pub fn games(&self, systems: &SystemList) -> Vec<Game> {
+		match self {
+			Self::RecentlyPlayed => {
+				let mut games = systems.collect(|_, x| x.lastplayed.is_some());
+				SortMode::LastPlayed.sort(&mut games);
+				games.truncate(COLLECTION_LIMIT);
+				games
+			}
+			Self::MostPlayed => {
+				let mut games = systems.collect(|_, x| x.playcount.unwrap_or_default() > 0);
+				SortMode::PlayCount.sort(&mut games);
+				games.truncate(COLLECTION_LIMIT);
+				games
+			}
+			Self::AllGames => {
+				let mut games = systems.collect(|_, _| true);
+				SortMode::Name.sort(&mut games);
+				games
+			}
+			Self::LastAdded => {
+				let mut games: Vec<(SystemTime, Game)> = systems
+					.collect(|_, _| true)
+					.into_iter()
+					.filter_map(|game| Some((game.modified?, game)))
+					.collect();
+				games.sort_by(|(a, _), (b, _)| b.cmp(a));
+				games.truncate(COLLECTION_LIMIT);
+				games.into_iter().map(|(_, game)| game).collect()
+			}
+		}
+	}
+}
+
+impl std::fmt::Display for AutoCollection {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Self::RecentlyPlayed => "Recently Played",
+			Self::MostPlayed => "Most Played",
+			Self::AllGames => "All Games",
+			Self::LastAdded => "Last Added",
+		})
+	}
+}
+
+/// A collection put together by the player. Stored the way ES-DE and EmulationStation store
+/// them: a `custom-<name>.cfg` file with the path of one ROM per line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomCollection {
+	pub name: String,
+	pub filename: PathBuf,
+	/// The lines of the file as read. Entries that match no game are kept, so rewriting the file
+	/// doesn't lose games from systems that aren't set up here.
+	pub entries: Vec<String>,
+}
+
+impl CustomCollection {
+	pub fn new(dir: &Path, name: &str) -> Self {
+		Self {
+			name: name.to_string(),
+			filename: dir.join(format!("{}{}.cfg", CUSTOM_COLLECTION_PREFIX, name)),
+			entries: Vec::new(),
+		}
+	}
+
+	pub fn from_file(filename: &Path) -> Result<Self> {
+		let name = filename
+			.file_stem()
+			.map(|x| x.to_string_lossy().to_string())
+			.unwrap_or_default();
+
+		Ok(Self {
+			name: name
+				.strip_prefix(CUSTOM_COLLECTION_PREFIX)
+				.unwrap_or(&name)
+				.to_string(),
+			filename: filename.to_path_buf(),
+			entries: std::fs::read_to_string(filename)?
+				.lines()
+				.map(str::trim)
+				.filter(|x| !x.is_empty())
+				.map(ToString::to_string)
+				.collect(),
+		})
+	}
+
+	pub fn to_file(&self) -> Result<()> {
+		if let Some(parent) = self.filename.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+
+		let mut tmp = self.filename.clone().into_os_string();
+		tmp.push(".tmp");
+
+		let mut out = self.entries.join("\n");
+		out.push('\n');
+
+		std::fs::write(&tmp, out)?;
+		std::fs::rename(&tmp, &self.filename)?;
+
+		Ok(())
+	}
+
+	/// The name of the collection's system.
+	pub fn system_name(&self) -> String {
+		format!("{}{}", CUSTOM_COLLECTION_PREFIX, self.name)
+	}
+
+	/// Whether the collection lists the ROM at `rom`, an absolute path.
+	pub fn contains(&self, rom: &Path) -> bool {
+		self.entries.iter().any(|x| entry_matches(x, rom))
+	}
+
+	/// Adds the ROM at `rom` if it isn't listed yet. Returns whether it was added.
+	pub fn add(&mut self, rom: &Path) -> bool {
+		if self.contains(rom) {
+			return false;
+		}
+
+		self.entries.push(rom.to_string_lossy().to_string());
+		true
+	}
+
+	pub fn remove(&mut self, rom: &Path) {
+		self.entries.retain(|x| !entry_matches(x, rom));
+	}
+}
+
+fn entry_matches(entry: &str, rom: &Path) -> bool {
+	match entry.strip_prefix(ROMPATH) {
+		// the ROM directory isn't known here, so the rest of the path has to match
+		Some(rest) => rom.to_string_lossy().ends_with(rest),
+		None => Path::new(entry) == rom,
+	}
+}
+
+/// Every custom collection in `dir`, sorted by name. Unreadable files are skipped.
+pub fn load_custom_collections(dir: &Path) -> Vec<CustomCollection> {
+	let mut collections: Vec<CustomCollection> = std::fs::read_dir(dir)
+		.into_iter()
+		.flatten()
+		.flatten()
+		.map(|x| x.path())
+		.filter(|x| {
+			x.extension().is_some_and(|x| x == "cfg")
+				&& x.file_name()
+					.is_some_and(|x| x.to_string_lossy().starts_with(CUSTOM_COLLECTION_PREFIX))
+		})
+		.filter_map(|x| match CustomCollection::from_file(&x) {
+			Ok(collection) => Some(collection),
+			Err(e) => {
+				tracing::warn!("skipping collection {}: {}", x.display(), e);
+				None
+			}
+		})
+		.collect();
+
+	collections.sort_by(|a, b| a.name.cmp(&b.name));
+	collections
+}
+
+impl SystemList {
+	/// Rebuilds the collections generated from the games of the real systems. Collections with
+	/// no games, or turned off in `config`, are left out.
+	pub fn refresh_collections(&mut self, config: &Config) {
+		self.kid_games_only = config.ui_mode == UiMode::Kid;
+
+		let mut favorites = self.collect(|_, x| x.favorite.unwrap_or_default());
+		SortMode::Name.sort(&mut favorites);
+		self.set_collection(FAVORITES_SYSTEM, "Favorites", favorites);
+
+		for collection in AutoCollection::all() {
+			let games = if config.collections.contains(&collection) {
+				collection.games(self)
+			} else {
+				Vec::new()
+			};
+
+			self.set_collection(collection.system_name(), &collection.to_string(), games);
+		}
+
+		let custom: Vec<String> = self.custom.iter().map(|x| x.system_name()).collect();
+		self.system.retain(|x| {
+			!x.is_virtual
+				|| !x.name.starts_with(CUSTOM_COLLECTION_PREFIX)
+				|| custom.contains(&x.name)
+		});
+
+		for collection in self.custom.clone() {
+			let mut games = self.collect(|system, game| {
+				system
+					.rom_path(game)
+					.is_some_and(|x| collection.contains(&x))
+			});
+			SortMode::Name.sort(&mut games);
+			self.set_collection(&collection.system_name(), &collection.name, games);
+		}
+	}
+
+	/// Puts the virtual systems in front of the real ones: favorites, the automatic collections,
+	/// the custom ones and then search results.
+	pub(crate) fn order_virtual(&mut self) {
+		let mut order = vec![FAVORITES_SYSTEM.to_string()];
+		order.extend(
+			AutoCollection::all()
+				.iter()
+				.map(|x| x.system_name().to_string()),
+		);
+		order.extend(self.custom.iter().map(|x| x.system_name()));
+		order.push(SEARCH_SYSTEM.to_string());
+
+		// stable, so the real systems keep their order behind the virtual ones
+		self.system.sort_by_key(|x| match x.is_virtual {
+			true => order
+				.iter()
+				.position(|name| *name == x.name)
+				.unwrap_or(order.len()),
+			false => usize::MAX,
+		});
+	}
+
+	/// The visible games of every real system that `f` picks, each carrying its system's name.
+	pub fn collect(&self, f: impl Fn(&System, &Game) -> bool) -> Vec<Game> {
+		self.system
+			.iter()
+			.filter(|x| !x.is_virtual)
+			.flat_map(|system| {
+				system
+					.games
+					.iter()
+					.filter(|x| self.shows(x) && f(system, x))
+					.map(|game| {
+						let mut game = game.clone();
+						game.system = Some(system.name.clone());
+						game
+					})
+			})
+			.collect()
+	}
+
+	fn set_collection(&mut self, name: &str, fullname: &str, games: Vec<Game>) {
+		if games.is_empty() {
+			self.system.retain(|x| !(x.is_virtual && x.name == name));
+			return;
+		}
+
+		self.set_virtual(System::new_virtual(name, fullname, games));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_collections() {
+		let mut systems = SystemList::from_file("test-systems.xml".into()).unwrap();
+		let mut games = crate::GameList::from_file("test-gamelist.xml".into())
+			.unwrap()
+			.game;
+		for game in &mut games {
+			game.favorite = None;
+			game.playcount = None;
+			game.lastplayed = None;
+		}
+
+		for system in &mut systems.system {
+			system.games = games.clone();
+			system.refresh();
+		}
+
+		let config = Config {
+			collections: [AutoCollection::RecentlyPlayed, AutoCollection::AllGames].into(),
+			..Default::default()
+		};
+		let real = systems.system.len();
+
+		systems.refresh_collections(&config);
+		assert_eq!(systems.system.len(), real + 1);
+		assert_eq!(systems.system[0].name, "all");
+
+		let last = systems.system.len() - 1;
+		let path = systems.system[last].games[3].path.clone().unwrap();
+		let name = systems.system[last].name.clone();
+		systems
+			.update_game(&name, &path, |x| {
+				x.favorite = Some(true);
+				x.lastplayed = Some("20240101T000000".to_string());
+			})
+			.unwrap();
+		systems.refresh_collections(&config);
+
+		let names: Vec<&str> = systems.system[..3]
+			.iter()
+			.map(|x| x.name.as_str())
+			.collect();
+		assert_eq!(names, vec![FAVORITES_SYSTEM, "recent", "all"]);
+
+		let favorites = &systems.system[0];
+		assert_eq!(favorites.gamelist.len(), 1);
+		assert_eq!(favorites.gamelist[0].system, Some(name.clone()));
+		assert_eq!(
+			systems.origin(0, &favorites.gamelist[0]).unwrap().name,
+			name
+		);
+	}
+
+	#[test]
+	fn test_custom_collection_entries() {
+		let mut collection = CustomCollection {
+			name: "shmups".to_string(),
+			filename: "custom-shmups.cfg".into(),
+			entries: vec!["%ROMPATH%/snes/axelay.sfc".to_string()],
+		};
+
+		assert!(collection.contains(Path::new("/home/me/roms/snes/axelay.sfc")));
+		assert!(!collection.contains(Path::new("/home/me/roms/nes/axelay.sfc")));
+
+		let rom = Path::new("/home/me/roms/snes/r-type iii.sfc");
+		assert!(collection.add(rom));
+		assert!(!collection.add(rom));
+		collection.remove(Path::new("/home/me/roms/snes/axelay.sfc"));
+		assert_eq!(collection.entries, vec![rom.to_string_lossy().to_string()]);
+	}
+}
@@ -1,41 +1,70 @@
-use super::{App, Orientation, System};
+use super::{
+	ActiveTheme, App, Config, GameDetails, KeyboardBackend, KeyboardInput, MenuEntry, MenuView,
+	MetadataEditor, Orientation, SetupStatus, System, ThemeLayout,
+};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tauri::State;
 
 #[tauri::command]
-pub async fn setting_values(state: State<'_, App>) -> std::result::Result<Vec<String>, ()> {
-	tracing::debug!("settings values requested");
-	Ok(state.setting_values().await)
+pub async fn settings(state: State<'_, App>) -> std::result::Result<Config, ()> {
+	Ok(state.config.lock().await.clone())
 }
 
+/// Every setting with its type, value and bounds or options, for themes with their own settings
+/// screen.
 #[tauri::command]
-pub fn setting_types(state: State<'_, App>) -> Vec<String> {
-	tracing::debug!("settings types requested");
-	state.settings_types()
+pub async fn settings_menu(state: State<'_, App>) -> std::result::Result<Vec<MenuEntry>, ()> {
+	Ok(state.settings_menu().await)
 }
 
+/// The theme in use, with its stylesheet, assets and system banners.
 #[tauri::command]
-pub fn settings_menu(state: State<'_, App>) -> Vec<String> {
-	tracing::debug!("settings menu requested");
-	state
-		.settings_menu()
-		.iter()
-		.map(ToString::to_string)
-		.collect()
+pub async fn active_theme(state: State<'_, App>) -> std::result::Result<ActiveTheme, ()> {
+	Ok(state.active_theme().await)
 }
 
+/// The EmulationStation layout of the active theme for a system, by name, or the current system.
+/// `None` when the active theme isn't an EmulationStation theme.
 #[tauri::command]
-pub fn menu(state: State<'_, App>) -> Vec<String> {
-	state.menu().iter().map(ToString::to_string).collect()
+pub async fn theme_layout(
+	state: State<'_, App>, system: Option<String>,
+) -> std::result::Result<Option<ThemeLayout>, ()> {
+	let system = {
+		// orientation before all_systems, like everywhere else
+		let current = state.orientation.lock().await.system_index;
+		let systems = state.all_systems.lock().await;
+		match system {
+			Some(name) => systems.system.iter().find(|x| x.name == name).cloned(),
+			None => systems.system.get(current).cloned(),
+		}
+	};
+
+	match system {
+		Some(system) => Ok(state.theme_layout(&system).await),
+		None => Ok(None),
+	}
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[tauri::command]
+pub async fn controllers_menu(state: State<'_, App>) -> std::result::Result<Vec<String>, ()> {
+	Ok(state.controllers_menu().await)
+}
+
+/// The open level of the menu, or `None` when it is closed.
+#[tauri::command]
+pub async fn current_menu(state: State<'_, App>) -> std::result::Result<Option<MenuView>, ()> {
+	let path = state.orientation.lock().await.menu_path.clone();
+	Ok(state.menu_view(&path).await)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AssetType {
 	#[default]
 	Image,
 	Thumbnail,
+	Marquee,
 	Video,
 }
 
@@ -45,11 +74,17 @@ pub async fn current_asset(
 ) -> std::result::Result<Option<PathBuf>, ()> {
 	let systems = state.all_systems.lock().await.system.clone();
 	let orientation = state.orientation.lock().await;
-	let current_system = &systems[orientation.system_index];
-	let current_game = &current_system.gamelist[orientation.gamelist_index];
+	let Some(current_game) = systems
+		.get(orientation.system_index)
+		.and_then(|x| x.gamelist.get(orientation.gamelist_index))
+	else {
+		return Ok(None);
+	};
+
 	let filename: Option<PathBuf> = match asset_type {
 		AssetType::Image => current_game.image.clone(),
 		AssetType::Thumbnail => current_game.thumbnail.clone(),
+		AssetType::Marquee => current_game.marquee.clone(),
 		AssetType::Video => current_game.video.clone(),
 	};
 	Ok(filename)
@@ -76,8 +111,14 @@ pub async fn current_text(
 ) -> std::result::Result<Option<String>, ()> {
 	let systems = state.all_systems.lock().await.system.clone();
 	let orientation = state.orientation.lock().await;
-	let current_system = &systems[orientation.system_index];
-	let current_game = current_system.gamelist[orientation.gamelist_index].clone();
+	let Some(current_game) = systems
+		.get(orientation.system_index)
+		.and_then(|x| x.gamelist.get(orientation.gamelist_index))
+		.cloned()
+	else {
+		return Ok(None);
+	};
+
 	Ok(match text_type {
 		TextType::Description => current_game.desc,
 		TextType::Rating => current_game.rating,
@@ -91,6 +132,28 @@ pub async fn current_text(
 	})
 }
 
+/// The selected game's details, or `None` when they aren't open.
+#[tauri::command]
+pub async fn current_details(
+	state: State<'_, App>,
+) -> std::result::Result<Option<GameDetails>, ()> {
+	Ok(state.game_details().await)
+}
+
+/// The selected game's metadata while the editor is open, or `None`.
+#[tauri::command]
+pub async fn current_editor(
+	state: State<'_, App>,
+) -> std::result::Result<Option<MetadataEditor>, ()> {
+	Ok(state.metadata_editor().await)
+}
+
+/// What is missing before there are games to show, for the first-run screen.
+#[tauri::command]
+pub async fn setup_status(state: State<'_, App>) -> std::result::Result<SetupStatus, ()> {
+	Ok(state.setup_status().await)
+}
+
 #[tauri::command]
 pub async fn all_systems(state: State<'_, App>) -> std::result::Result<Vec<System>, ()> {
 	Ok(state.all_systems.clone().lock_owned().await.system.clone())
@@ -100,3 +163,19 @@ pub async fn all_systems(state: State<'_, App>) -> std::result::Result<Vec<Syste
 pub async fn current_orientation(state: State<'_, App>) -> std::result::Result<Orientation, ()> {
 	Ok(state.orientation.clone().lock_owned().await.clone())
 }
+
+/// The key names [keyboard_input] does something with.
+#[tauri::command]
+pub fn keyboard_keys() -> Vec<String> {
+	KeyboardBackend::keys()
+}
+
+#[tauri::command]
+pub async fn keyboard_input(
+	state: State<'_, App>, keyboard: State<'_, KeyboardInput>, key: String,
+) -> std::result::Result<(), ()> {
+	if state.config.lock().await.enable_keyboard {
+		keyboard.send(key).await;
+	}
+	Ok(())
+}
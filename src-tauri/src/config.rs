@@ -1,10 +1,14 @@
+use crate::{AutoCollection, GameFilter, InputBindings, InputEvent, SortMode};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::PathBuf,
+};
 
 pub const DEFAULT_CONFIG_FILENAME: &str = "rawrcade/config.json";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum LogLevel {
 	Trace,
@@ -14,6 +18,30 @@ pub enum LogLevel {
 	Error,
 }
 
+impl LogLevel {
+	pub fn all() -> Vec<Self> {
+		vec![
+			Self::Trace,
+			Self::Debug,
+			Self::Info,
+			Self::Warn,
+			Self::Error,
+		]
+	}
+}
+
+impl std::fmt::Display for LogLevel {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			LogLevel::Trace => "Trace",
+			LogLevel::Debug => "Debug",
+			LogLevel::Info => "Info",
+			LogLevel::Warn => "Warning",
+			LogLevel::Error => "Error",
+		})
+	}
+}
+
 impl From<LogLevel> for tracing::Level {
 	fn from(value: LogLevel) -> tracing::Level {
 		match value {
@@ -26,13 +54,99 @@ impl From<LogLevel> for tracing::Level {
 	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How much of the frontend is open to whoever is playing, as in ES-DE.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UiMode {
+	#[default]
+	Full,
+	/// No settings, no leaving the frontend and no changes to games, for cabinets in public.
+	Kiosk,
+	/// Kiosk, showing only games marked as `kidgame`.
+	Kid,
+}
+
+impl UiMode {
+	pub fn all() -> Vec<Self> {
+		vec![Self::Full, Self::Kiosk, Self::Kid]
+	}
+
+	/// Whether settings, exiting and changes to games are locked away.
+	pub fn is_restricted(&self) -> bool {
+		*self != Self::Full
+	}
+}
+
+impl std::fmt::Display for UiMode {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			UiMode::Full => "Full",
+			UiMode::Kiosk => "Kiosk",
+			UiMode::Kid => "Kid",
+		})
+	}
+}
+
+/// What the screensaver shows once the frontend has been left alone for a while.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreensaverType {
+	/// Darkens the screen.
+	#[default]
+	Dim,
+	/// Images of random games.
+	Slideshow,
+	/// Video snaps of random games.
+	Video,
+}
+
+impl ScreensaverType {
+	pub fn all() -> Vec<Self> {
+		vec![Self::Dim, Self::Slideshow, Self::Video]
+	}
+}
+
+impl std::fmt::Display for ScreensaverType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			ScreensaverType::Dim => "Dim",
+			ScreensaverType::Slideshow => "Slideshow",
+			ScreensaverType::Video => "Video",
+		})
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
 	pub swap_confirm: bool,
 	pub start_fullscreen: bool,
 	pub theme: Option<String>,
 	pub enable_keyboard: bool,
 	pub log_level: LogLevel,
+	pub primary_controller_only: bool,
+	pub bindings: InputBindings,
+	pub letter_overlay: bool,
+	/// Percent, for videos and sounds played by the theme.
+	pub volume: u8,
+	/// How many games Page Up and Page Down move by.
+	pub page_size: usize,
+	/// Minutes of inactivity before the screensaver starts; 0 turns it off.
+	pub screensaver_delay: u64,
+	pub screensaver: ScreensaverType,
+	/// Ok launches the game the screensaver is showing, rather than only waking up.
+	pub screensaver_launch: bool,
+	/// Launches randomly picked games after a short countdown.
+	pub party_mode: bool,
+	pub ui_mode: UiMode,
+	/// The buttons to press, in order, to go back to [UiMode::Full] from the other modes.
+	pub unlock_pin: Vec<InputEvent>,
+	/// The order of each system's games, by system name.
+	pub sort: BTreeMap<String, SortMode>,
+	/// Which games each system shows, by system name.
+	pub filters: BTreeMap<String, GameFilter>,
+	/// The automatic collections shown.
+	pub collections: BTreeSet<AutoCollection>,
 }
 
 impl Default for Config {
@@ -43,11 +157,54 @@ impl Default for Config {
 			theme: None,
 			enable_keyboard: false,
 			log_level: LogLevel::Debug,
+			primary_controller_only: false,
+			bindings: InputBindings::default(),
+			letter_overlay: true,
+			volume: 80,
+			page_size: 10,
+			screensaver_delay: 5,
+			screensaver: ScreensaverType::Dim,
+			screensaver_launch: false,
+			party_mode: false,
+			ui_mode: UiMode::Full,
+			// the Konami code, like ES-DE
+			unlock_pin: vec![
+				InputEvent::Up,
+				InputEvent::Up,
+				InputEvent::Down,
+				InputEvent::Down,
+				InputEvent::Left,
+				InputEvent::Right,
+				InputEvent::Left,
+				InputEvent::Right,
+				InputEvent::Cancel,
+				InputEvent::Ok,
+			],
+			sort: BTreeMap::new(),
+			filters: BTreeMap::new(),
+			collections: BTreeSet::from([
+				AutoCollection::RecentlyPlayed,
+				AutoCollection::MostPlayed,
+				AutoCollection::LastAdded,
+			]),
 		}
 	}
 }
 
 impl Config {
+	pub fn sort_mode(&self, system: &str) -> SortMode {
+		self.sort.get(system).copied().unwrap_or_default()
+	}
+
+	pub fn filter(&self, system: &str) -> GameFilter {
+		self.filters.get(system).cloned().unwrap_or_default()
+	}
+
+	/// The filter of `system`, to change in place.
+	pub fn filter_mut(&mut self, system: &str) -> &mut GameFilter {
+		self.filters.entry(system.to_string()).or_default()
+	}
+
 	pub fn from_file(filename: &PathBuf) -> Result<Self> {
 		let f = std::fs::OpenOptions::new().read(true).open(filename)?;
 
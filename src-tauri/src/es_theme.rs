@@ -0,0 +1,550 @@
+use anyhow::{Result, anyhow};
+use quick_xml::events::Event as XmlEvent;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+/// The file an EmulationStation theme is described by, at the top of the theme or in a folder
+/// per system.
+pub const ES_THEME_FILE: &str = "theme.xml";
+
+// includes nested deeper than this are assumed to be a cycle
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+// gamelist views of the original EmulationStation, in the order they are preferred when filling
+// in the ES-DE `gamelist` view
+const LEGACY_GAMELIST_VIEWS: &[&str] = &["detailed", "video", "grid", "basic"];
+
+/// What a theme is being resolved for. Variants, color schemes and aspect ratios left unset use
+/// the first one the theme defines.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EsThemeOptions {
+	/// The system's `<name>`, as `${system.name}`.
+	pub system_name: String,
+	/// The system's `<fullname>`, as `${system.fullName}`.
+	pub system_full_name: String,
+	/// The system's theme folder, as `${system.theme}`.
+	pub system_theme: String,
+	pub variant: Option<String>,
+	pub color_scheme: Option<String>,
+	pub aspect_ratio: Option<String>,
+}
+
+/// A theme element with its properties resolved: variables substituted, paths made absolute and
+/// colors turned into CSS `#rrggbbaa` notation. Positions and sizes are fractions of the screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutElement {
+	pub name: String,
+	/// The element's tag, like `image`, `text` or `textlist`.
+	pub kind: String,
+	/// Elements the theme added, as opposed to ones that restyle a built-in element.
+	pub extra: bool,
+	pub pos: Option<[f32; 2]>,
+	pub size: Option<[f32; 2]>,
+	pub origin: Option<[f32; 2]>,
+	pub z_index: f32,
+	pub visible: bool,
+	/// Every property as written in the theme, after resolving.
+	pub properties: BTreeMap<String, String>,
+}
+
+/// The elements of one view, in drawing order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewLayout {
+	pub elements: Vec<LayoutElement>,
+}
+
+/// A resolved theme, keyed by view name (`system`, `gamelist`, and for older themes also
+/// `basic`, `detailed`, `video` and `grid`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThemeLayout {
+	pub views: BTreeMap<String, ViewLayout>,
+}
+
+/// Resolves the theme in `dir` for a system, using `<dir>/<system theme>/theme.xml` when the
+/// theme has a folder for the system and `<dir>/theme.xml` otherwise.
+pub fn load_es_theme(dir: &Path, options: &EsThemeOptions) -> Result<ThemeLayout> {
+	let per_system = dir.join(&options.system_theme).join(ES_THEME_FILE);
+	let file = if !options.system_theme.is_empty() && per_system.is_file() {
+		per_system
+	} else {
+		dir.join(ES_THEME_FILE)
+	};
+
+	let mut resolver = Resolver::new(options);
+	resolver.load_file(&file, 0)?;
+	Ok(resolver.into_layout())
+}
+
+/// Whether `dir` looks like an EmulationStation theme.
+pub fn is_es_theme(dir: &Path) -> bool {
+	dir.join(ES_THEME_FILE).is_file()
+		|| std::fs::read_dir(dir)
+			.into_iter()
+			.flatten()
+			.flatten()
+			.any(|x| x.path().join(ES_THEME_FILE).is_file())
+}
+
+/// An XML element before any theme semantics are applied.
+#[derive(Debug, Clone, Default)]
+struct XmlNode {
+	name: String,
+	attributes: BTreeMap<String, String>,
+	text: String,
+	children: Vec<XmlNode>,
+}
+
+impl XmlNode {
+	fn attribute(&self, name: &str) -> Option<&str> {
+		self.attributes.get(name).map(String::as_str)
+	}
+}
+
+fn parse_xml(text: &str) -> Result<XmlNode> {
+	let mut reader = quick_xml::Reader::from_str(text);
+	let mut stack: Vec<XmlNode> = Vec::new();
+	let mut root = None;
+
+	let open = |e: &quick_xml::events::BytesStart| -> Result<XmlNode> {
+		let mut node = XmlNode {
+			name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
+			..Default::default()
+		};
+
+		for attribute in e.attributes() {
+			let attribute = attribute?;
+			node.attributes.insert(
+				String::from_utf8_lossy(attribute.key.as_ref()).to_string(),
+				attribute.unescape_value()?.to_string(),
+			);
+		}
+
+		Ok(node)
+	};
+
+	loop {
+		match reader.read_event()? {
+			XmlEvent::Start(e) => stack.push(open(&e)?),
+			XmlEvent::Empty(e) => {
+				let node = open(&e)?;
+				match stack.last_mut() {
+					Some(parent) => parent.children.push(node),
+					None => root = Some(node),
+				}
+			}
+			XmlEvent::End(_) => {
+				let mut node = stack.pop().ok_or(anyhow!("unbalanced closing tag"))?;
+				node.text = node.text.trim().to_string();
+				match stack.last_mut() {
+					Some(parent) => parent.children.push(node),
+					None => root = Some(node),
+				}
+			}
+			XmlEvent::Text(e) => {
+				if let Some(node) = stack.last_mut() {
+					node.text.push_str(&e.xml_content()?);
+				}
+			}
+			XmlEvent::CData(e) => {
+				if let Some(node) = stack.last_mut() {
+					node.text.push_str(&e.xml_content()?);
+				}
+			}
+			XmlEvent::GeneralRef(e) => {
+				if let Some(node) = stack.last_mut() {
+					if let Some(c) = e.resolve_char_ref()? {
+						node.text.push(c);
+					} else if let Some(s) =
+						quick_xml::escape::resolve_predefined_entity(&e.decode()?)
+					{
+						node.text.push_str(s);
+					}
+				}
+			}
+			XmlEvent::Eof => break,
+			_ => {}
+		}
+	}
+
+	root.ok_or(anyhow!("document has no root element"))
+}
+
+fn split_names(names: &str) -> impl Iterator<Item = &str> {
+	names.split(',').map(str::trim).filter(|x| !x.is_empty())
+}
+
+// properties holding file names, which are relative to the file they are written in
+fn is_path_property(key: &str) -> bool {
+	key == "path" || key == "default" || key.ends_with("Path") || key.ends_with("Image")
+}
+
+fn is_color_property(key: &str) -> bool {
+	key.to_lowercase().contains("color")
+}
+
+fn parse_pair(value: &str) -> Option<[f32; 2]> {
+	let mut parts = value.split_whitespace().map(|x| x.parse::<f32>().ok());
+	Some([parts.next()??, parts.next()??])
+}
+
+/// `RRGGBB` or `RRGGBBAA` to `#rrggbbaa`; anything else is left alone.
+fn css_color(value: &str) -> String {
+	let hex = value.trim_start_matches('#');
+	if !hex.chars().all(|x| x.is_ascii_hexdigit()) {
+		return value.to_string();
+	}
+
+	match hex.len() {
+		6 => format!("#{}ff", hex.to_lowercase()),
+		8 => format!("#{}", hex.to_lowercase()),
+		_ => value.to_string(),
+	}
+}
+
+// drawing order for elements that don't set `zIndex`, following ES-DE
+fn default_z_index(kind: &str) -> f32 {
+	match kind {
+		"image" | "video" => 30.0,
+		"text" | "datetime" | "carousel" | "grid" => 40.0,
+		"rating" => 45.0,
+		"textlist" | "helpsystem" => 50.0,
+		_ => 35.0,
+	}
+}
+
+#[derive(Debug, Default)]
+struct PendingElement {
+	name: String,
+	kind: String,
+	extra: bool,
+	properties: BTreeMap<String, String>,
+}
+
+struct Resolver {
+	variables: BTreeMap<String, String>,
+	variant: Option<String>,
+	color_scheme: Option<String>,
+	aspect_ratio: Option<String>,
+	views: BTreeMap<String, Vec<PendingElement>>,
+}
+
+impl Resolver {
+	fn new(options: &EsThemeOptions) -> Self {
+		let variables = BTreeMap::from([
+			("system.name".to_string(), options.system_name.clone()),
+			(
+				"system.fullName".to_string(),
+				options.system_full_name.clone(),
+			),
+			("system.theme".to_string(), options.system_theme.clone()),
+		]);
+
+		Self {
+			variables,
+			variant: options.variant.clone(),
+			color_scheme: options.color_scheme.clone(),
+			aspect_ratio: options.aspect_ratio.clone(),
+			views: BTreeMap::new(),
+		}
+	}
+
+	fn load_file(&mut self, file: &Path, depth: usize) -> Result<()> {
+		if depth > MAX_INCLUDE_DEPTH {
+			return Err(anyhow!("includes nested too deeply at {}", file.display()));
+		}
+
+		let root = parse_xml(&std::fs::read_to_string(file)?)
+			.map_err(|e| anyhow!("{}: {}", file.display(), e))?;
+
+		if root.name != "theme" {
+			return Err(anyhow!("{}: not a theme file", file.display()));
+		}
+
+		let dir = file.parent().unwrap_or(Path::new("/")).to_path_buf();
+		self.apply_block(&root, &dir, depth)
+	}
+
+	fn apply_block(&mut self, node: &XmlNode, dir: &Path, depth: usize) -> Result<()> {
+		for child in &node.children {
+			match child.name.as_str() {
+				"variables" => {
+					for variable in &child.children {
+						let value = self.substitute(&variable.text);
+						self.variables.insert(variable.name.clone(), value);
+					}
+				}
+				"include" => {
+					let file = self.resolve_path(&self.substitute(&child.text), dir);
+					// a missing include is common in themes that include optional per-system
+					// files, so it isn't an error
+					if file.is_file() {
+						self.load_file(&file, depth + 1)?;
+					} else {
+						tracing::debug!("theme include not found: {}", file.display());
+					}
+				}
+				"view" => {
+					let names = child.attribute("name").unwrap_or_default();
+					for view in split_names(names) {
+						self.apply_view(view, child, dir);
+					}
+				}
+				"variant" | "colorScheme" | "aspectRatio" if self.selects(child) => {
+					self.apply_block(child, dir, depth)?
+				}
+				// older themes gate elements on features; all of them are supported
+				"feature" => self.apply_block(child, dir, depth)?,
+				_ => {}
+			}
+		}
+
+		Ok(())
+	}
+
+	// whether a variant, color scheme or aspect ratio block applies. The first one seen is
+	// picked when none was asked for.
+	fn selects(&mut self, node: &XmlNode) -> bool {
+		let names: Vec<&str> = split_names(node.attribute("name").unwrap_or_default()).collect();
+		if names.contains(&"all") {
+			return true;
+		}
+
+		let selected = match node.name.as_str() {
+			"variant" => &mut self.variant,
+			"colorScheme" => &mut self.color_scheme,
+			_ => &mut self.aspect_ratio,
+		};
+
+		if selected.is_none() {
+			*selected = names.first().map(ToString::to_string);
+		}
+
+		selected.as_deref().is_some_and(|x| names.contains(&x))
+	}
+
+	fn apply_view(&mut self, view: &str, node: &XmlNode, dir: &Path) {
+		for element in &node.children {
+			let names = element.attribute("name").unwrap_or_default().to_string();
+			let extra = element.attribute("extra") == Some("true");
+
+			let properties: Vec<(String, String)> = element
+				.children
+				.iter()
+				.map(|x| {
+					let mut value = self.substitute(&x.text);
+					if is_path_property(&x.name) && !value.is_empty() {
+						value = self.resolve_path(&value, dir).display().to_string();
+					}
+
+					(x.name.clone(), value)
+				})
+				.collect();
+
+			let elements = self.views.entry(view.to_string()).or_default();
+			for name in split_names(&names) {
+				let index = match elements
+					.iter()
+					.position(|x| x.name == name && x.kind == element.name)
+				{
+					Some(index) => index,
+					None => {
+						elements.push(PendingElement {
+							name: name.to_string(),
+							kind: element.name.clone(),
+							..Default::default()
+						});
+						elements.len() - 1
+					}
+				};
+
+				let pending = &mut elements[index];
+				pending.extra |= extra;
+				pending.properties.extend(properties.iter().cloned());
+			}
+		}
+	}
+
+	fn substitute(&self, text: &str) -> String {
+		let mut out = String::new();
+		let mut rest = text;
+
+		while let Some(start) = rest.find("${") {
+			out.push_str(&rest[..start]);
+			let Some(end) = rest[start..].find('}') else {
+				break;
+			};
+
+			let name = &rest[start + 2..start + end];
+			match self.variables.get(name) {
+				Some(value) => out.push_str(value),
+				None => tracing::debug!("undefined theme variable: {}", name),
+			}
+
+			rest = &rest[start + end + 1..];
+		}
+
+		out.push_str(rest);
+		out
+	}
+
+	fn resolve_path(&self, path: &str, dir: &Path) -> PathBuf {
+		if let Some(rest) = path.strip_prefix("~/") {
+			return dirs::home_dir().unwrap_or("/".into()).join(rest);
+		}
+
+		// `:/` paths name resources built into EmulationStation; the frontend decides what to do
+		// with them
+		if path.starts_with(":/") {
+			return PathBuf::from(path);
+		}
+
+		let mut resolved = dir.to_path_buf();
+		for component in Path::new(path).components() {
+			match component {
+				std::path::Component::CurDir => {}
+				std::path::Component::ParentDir => {
+					resolved.pop();
+				}
+				other => resolved.push(other),
+			}
+		}
+
+		resolved
+	}
+
+	fn into_layout(self) -> ThemeLayout {
+		let mut views: BTreeMap<String, ViewLayout> = self
+			.views
+			.into_iter()
+			.map(|(name, elements)| {
+				let mut elements: Vec<LayoutElement> =
+					elements.into_iter().map(normalize_element).collect();
+				elements.sort_by(|a, b| a.z_index.total_cmp(&b.z_index));
+				(name, ViewLayout { elements })
+			})
+			.collect();
+
+		if !views.contains_key("gamelist") {
+			if let Some(legacy) = LEGACY_GAMELIST_VIEWS.iter().find_map(|x| views.get(*x)) {
+				views.insert("gamelist".to_string(), legacy.clone());
+			}
+		}
+
+		ThemeLayout { views }
+	}
+}
+
+fn normalize_element(element: PendingElement) -> LayoutElement {
+	let properties: BTreeMap<String, String> = element
+		.properties
+		.into_iter()
+		.map(|(key, value)| {
+			let value = if is_color_property(&key) {
+				css_color(&value)
+			} else {
+				value
+			};
+			(key, value)
+		})
+		.collect();
+
+	let pair = |key: &str| properties.get(key).and_then(|x| parse_pair(x));
+
+	LayoutElement {
+		pos: pair("pos"),
+		size: pair("size"),
+		origin: pair("origin"),
+		z_index: properties
+			.get("zIndex")
+			.and_then(|x| x.parse().ok())
+			.unwrap_or(default_z_index(&element.kind)),
+		visible: properties.get("visible").is_none_or(|x| x != "false"),
+		name: element.name,
+		kind: element.kind,
+		extra: element.extra,
+		properties,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_load_es_theme() {
+		let dir = std::env::temp_dir().join(format!("rawrcade-es-theme-{}", std::process::id()));
+		std::fs::create_dir_all(dir.join("nes")).unwrap();
+
+		std::fs::write(
+			dir.join("common.xml"),
+			r#"<theme>
+	<variables>
+		<accent>336699</accent>
+	</variables>
+	<view name="system, detailed">
+		<text name="title">
+			<pos>0.1 0.2</pos>
+			<color>${accent}</color>
+		</text>
+	</view>
+</theme>"#,
+		)
+		.unwrap();
+
+		std::fs::write(
+			dir.join("nes").join(ES_THEME_FILE),
+			r#"<theme>
+	<include>./../common.xml</include>
+	<variant name="dark, light">
+		<view name="detailed">
+			<image name="logo" extra="true">
+				<path>./art/${system.theme}.png</path>
+				<zIndex>10</zIndex>
+			</image>
+		</view>
+	</variant>
+	<variant name="light">
+		<view name="detailed">
+			<text name="title"><visible>false</visible></text>
+		</view>
+	</variant>
+</theme>"#,
+		)
+		.unwrap();
+
+		let options = EsThemeOptions {
+			system_name: "nes".to_string(),
+			system_theme: "nes".to_string(),
+			..Default::default()
+		};
+
+		let layout = load_es_theme(&dir, &options).unwrap();
+		let gamelist = &layout.views["gamelist"].elements;
+		assert_eq!(gamelist.len(), 2);
+
+		assert_eq!(gamelist[0].name, "logo");
+		assert!(gamelist[0].extra);
+		assert_eq!(
+			gamelist[0].properties["path"],
+			dir.join("nes/art/nes.png").display().to_string()
+		);
+
+		assert_eq!(gamelist[1].name, "title");
+		assert_eq!(gamelist[1].pos, Some([0.1, 0.2]));
+		assert_eq!(gamelist[1].properties["color"], "#336699ff");
+		// the light variant isn't the default, so the title stays visible
+		assert!(gamelist[1].visible);
+
+		let light = EsThemeOptions {
+			variant: Some("light".to_string()),
+			..options
+		};
+		let layout = load_es_theme(&dir, &light).unwrap();
+		assert!(!layout.views["detailed"].elements[1].visible);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,156 @@
+use crate::Game;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Narrows a system to the games matching every condition that is set. The default filter lets
+/// everything through.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameFilter {
+	/// One of the game's genres, or a sub-genre of it.
+	pub genre: Option<String>,
+	/// The game supports at least this many players.
+	pub players: Option<u32>,
+	pub year_from: Option<u32>,
+	pub year_to: Option<u32>,
+	/// Out of 5 stars.
+	pub min_rating: Option<u32>,
+	pub developer: Option<String>,
+	pub publisher: Option<String>,
+	pub favorites_only: bool,
+	/// Only games that have been played when true, or only unplayed ones when false.
+	pub played: Option<bool>,
+	/// Only games marked as `kidgame`. Set by [crate::UiMode::Kid] rather than kept per system.
+	#[serde(skip)]
+	pub kid_games_only: bool,
+}
+
+impl GameFilter {
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+
+	pub fn matches(&self, game: &Game) -> bool {
+		let same = |want: &Option<String>, have: &Option<String>| match want {
+			Some(want) => have
+				.as_deref()
+				.is_some_and(|x| x.trim().eq_ignore_ascii_case(want)),
+			None => true,
+		};
+
+		// games without the field don't match a condition on it
+		let at_least = |want: Option<u32>, have: Option<u32>| match want {
+			Some(want) => have.is_some_and(|x| x >= want),
+			None => true,
+		};
+
+		let genre = match &self.genre {
+			Some(want) => {
+				let want = want.to_lowercase();
+				let sub = format!("{want} /");
+				genres(game)
+					.iter()
+					.map(|x| x.to_lowercase())
+					.any(|x| x == want || x.starts_with(&sub))
+			}
+			None => true,
+		};
+
+		let year = release_year(game);
+
+		genre
+			&& at_least(self.players, max_players(game))
+			&& at_least(self.year_from, year)
+			&& self.year_to.is_none_or(|to| year.is_some_and(|x| x <= to))
+			&& at_least(self.min_rating.map(|x| x * 100), stars(game))
+			&& same(&self.developer, &game.developer)
+			&& same(&self.publisher, &game.publisher)
+			&& (!self.favorites_only || game.favorite.unwrap_or_default())
+			&& self
+				.played
+				.is_none_or(|played| (game.playcount.unwrap_or_default() > 0) == played)
+			&& (!self.kid_games_only || game.kidgame.unwrap_or_default())
+	}
+}
+
+/// The genres a game is listed under. Gamelists separate them with commas, and sub-genres are
+/// written as `Genre / Sub-genre`.
+pub fn genres(game: &Game) -> Vec<String> {
+	game.genre
+		.as_deref()
+		.unwrap_or_default()
+		.split(',')
+		.map(str::trim)
+		.filter(|x| !x.is_empty())
+		.map(ToString::to_string)
+		.collect()
+}
+
+/// Every distinct value of a field across `games`, sorted.
+pub fn field_values(games: &[Game], field: impl Fn(&Game) -> Vec<String>) -> Vec<String> {
+	games
+		.iter()
+		.filter(|x| !x.is_hidden())
+		.flat_map(field)
+		.collect::<BTreeSet<String>>()
+		.into_iter()
+		.collect()
+}
+
+// ES dates are `YYYYMMDDTHHMMSS`
+fn release_year(game: &Game) -> Option<u32> {
+	game.releasedate.as_deref()?.get(..4)?.parse().ok()
+}
+
+// `players` is written as "2" or "1-4"; the largest number is how many can play at once
+fn max_players(game: &Game) -> Option<u32> {
+	game.players
+		.as_deref()?
+		.split(|x: char| !x.is_ascii_digit())
+		.filter_map(|x| x.parse().ok())
+		.max()
+}
+
+// the rating is stored from 0 to 1; in hundredths of a star so half stars compare exactly
+fn stars(game: &Game) -> Option<u32> {
+	let rating: f64 = game.rating.as_deref()?.trim().parse().ok()?;
+	Some((rating * 500.0).round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_combined_filter() {
+		let game = |genre: &str, players: &str, date: &str, rating: &str| Game {
+			genre: Some(genre.to_string()),
+			players: Some(players.to_string()),
+			releasedate: Some(date.to_string()),
+			rating: Some(rating.to_string()),
+			..Default::default()
+		};
+
+		let filter = GameFilter {
+			genre: Some("Fighting".to_string()),
+			players: Some(2),
+			year_from: Some(1990),
+			year_to: Some(1999),
+			min_rating: Some(4),
+			..Default::default()
+		};
+
+		assert!(filter.matches(&game(
+			"Action, Fighting / 2D",
+			"1-2",
+			"19940101T000000",
+			"0.8"
+		)));
+		// too early, single player, rated too low, wrong genre
+		assert!(!filter.matches(&game("Fighting", "1-2", "19890101T000000", "0.8")));
+		assert!(!filter.matches(&game("Fighting", "1", "19940101T000000", "0.8")));
+		assert!(!filter.matches(&game("Fighting", "2", "19940101T000000", "0.7")));
+		assert!(!filter.matches(&game("Fighter Scrolling", "2", "19940101T000000", "0.9")));
+		assert!(GameFilter::default().matches(&Game::default()));
+	}
+}
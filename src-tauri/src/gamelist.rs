@@ -1,6 +1,16 @@
-use anyhow::Result;
+use crate::name_key;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use quick_xml::{
+	Reader, Writer,
+	events::{BytesStart, BytesText, Event},
+};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameList {
@@ -16,6 +26,314 @@ impl GameList {
 			std::fs::OpenOptions::new().read(true).open(filename)?,
 		))?)
 	}
+
+	/// Writes the list next to `filename` first and renames it into place, so a crash never
+	/// leaves a truncated gamelist behind.
+	pub fn to_file(&self, filename: &Path) -> Result<()> {
+		let mut out = String::from("<?xml version=\"1.0\"?>\n");
+		let mut ser = quick_xml::se::Serializer::with_root(&mut out, Some("gameList"))?;
+		ser.indent('\t', 1);
+		self.serialize(ser)?;
+		out.push('\n');
+
+		write_file(filename, out.as_bytes())
+	}
+
+	/// Writes `games` into the gamelist at `filename`, which is usually a scraped ES-DE one. Games
+	/// are matched by path and only the elements that changed are rewritten; everything [Game]
+	/// doesn't know about, attributes included, is kept as it is. Games that are gone are removed
+	/// and new ones are added at the end.
+	pub fn save_games(filename: &Path, games: &[Game]) -> Result<()> {
+		let mut nodes = parse_xml(&std::fs::read_to_string(filename)?)?;
+		let Some(root) = nodes.iter_mut().find_map(|x| match x {
+			Node::Element(x) if x.start.name().as_ref() == b"gameList" => Some(x),
+			_ => None,
+		}) else {
+			return Err(anyhow!("{} has no gameList", filename.display()));
+		};
+
+		let mut saved: Vec<bool> = vec![false; games.len()];
+		let mut index = 0;
+		while index < root.children.len() {
+			let old: Game = match &root.children[index] {
+				Node::Element(x) if x.start.name().as_ref() == b"game" => {
+					quick_xml::de::from_str(&x.to_xml()?)?
+				}
+				_ => {
+					index += 1;
+					continue;
+				}
+			};
+
+			let Some(path) = old.path.as_ref() else {
+				index += 1;
+				continue;
+			};
+
+			match games
+				.iter()
+				.enumerate()
+				.find(|(i, x)| !saved[*i] && x.path.as_ref() == Some(path))
+			{
+				Some((i, game)) => {
+					saved[i] = true;
+					if let Node::Element(element) = &mut root.children[index] {
+						element.patch(&old, game)?;
+					}
+					index += 1;
+				}
+				None => index = root.remove(index),
+			}
+		}
+
+		for (game, _) in games
+			.iter()
+			.zip(saved)
+			.filter(|(x, saved)| !saved && x.path.is_some())
+		{
+			let mut element = game_element(game)?;
+			element.lay_out(&root.indent(1));
+			root.append(Node::Element(element), 1);
+		}
+
+		write_file(filename, write_xml(&nodes)?.as_bytes())
+	}
+}
+
+// written next to `filename` first and renamed into place, so a crash never leaves a truncated
+// file behind
+fn write_file(filename: &Path, contents: &[u8]) -> Result<()> {
+	let mut tmp = filename.to_path_buf().into_os_string();
+	tmp.push(".tmp");
+
+	std::fs::write(&tmp, contents)?;
+	std::fs::rename(&tmp, filename)?;
+
+	Ok(())
+}
+
+// a gamelist as read, so it can be written back without losing anything
+enum Node {
+	Element(Element),
+	/// Text, comments and everything else, as escaped in the file.
+	Event(Event<'static>),
+}
+
+impl Node {
+	fn is_whitespace(&self) -> bool {
+		matches!(self, Node::Event(Event::Text(x)) if x.iter().all(u8::is_ascii_whitespace))
+	}
+}
+
+struct Element {
+	start: BytesStart<'static>,
+	children: Vec<Node>,
+	/// Written as `<tag/>` while it has no children.
+	empty: bool,
+}
+
+impl Element {
+	/// The whitespace before the element's children, guessed from the first child when it has
+	/// any. `depth` is how deep the element is in the document.
+	fn indent(&self, depth: usize) -> String {
+		self.children
+			.iter()
+			.zip(self.children.iter().skip(1))
+			.find_map(|(space, element)| match (space, element) {
+				(Node::Event(Event::Text(x)), Node::Element(_)) if space.is_whitespace() => {
+					Some(String::from_utf8_lossy(x).to_string())
+				}
+				_ => None,
+			})
+			.unwrap_or_else(|| format!("\n{}", "\t".repeat(depth)))
+	}
+
+	/// Adds `node` as the last child, before the whitespace closing the element.
+	fn append(&mut self, node: Node, depth: usize) {
+		let indent = self.indent(depth);
+		if !self.children.last().is_some_and(Node::is_whitespace) {
+			self.children
+				.push(Node::Event(Event::Text(BytesText::from_escaped(format!(
+					"\n{}",
+					"\t".repeat(depth - 1)
+				)))));
+		}
+
+		let at = self.children.len() - 1;
+		self.children.insert(at, node);
+		self.children.insert(
+			at,
+			Node::Event(Event::Text(BytesText::from_escaped(indent))),
+		);
+		self.empty = false;
+	}
+
+	/// Removes the child at `index` with the whitespace before it, returning the index of the
+	/// child that followed it.
+	fn remove(&mut self, index: usize) -> usize {
+		self.children.remove(index);
+		if index > 0 && self.children[index - 1].is_whitespace() {
+			self.children.remove(index - 1);
+			return index - 1;
+		}
+
+		index
+	}
+
+	/// Lays out the children of an element built from [Game], which has none.
+	fn lay_out(&mut self, indent: &str) {
+		let inner = format!("{}\t", indent);
+		let children = std::mem::take(&mut self.children);
+		for child in children {
+			self.children
+				.push(Node::Event(Event::Text(BytesText::from_escaped(
+					inner.clone(),
+				))));
+			self.children.push(child);
+		}
+		self.children
+			.push(Node::Event(Event::Text(BytesText::from_escaped(
+				indent.to_string(),
+			))));
+	}
+
+	/// Rewrites the elements of this `<game>` that differ between `old`, the game as read from
+	/// it, and `new`.
+	fn patch(&mut self, old: &Game, new: &Game) -> Result<()> {
+		let old = game_fields(old)?;
+		let mut new = game_fields(new)?;
+		let depth = 2;
+
+		let names: BTreeSet<String> = old.keys().chain(new.keys()).cloned().collect();
+		for name in names {
+			if old.get(&name).map(|x| &x.1) == new.get(&name).map(|x| &x.1) {
+				continue;
+			}
+
+			let mut replacement = new.remove(&name).map(|x| x.0);
+			let mut index = 0;
+			while index < self.children.len() {
+				match &self.children[index] {
+					Node::Element(x) if x.start.name().as_ref() == name.as_bytes() => {
+						match replacement.take() {
+							Some(element) => {
+								self.children[index] = Node::Element(element);
+								index += 1;
+							}
+							None => index = self.remove(index),
+						}
+					}
+					_ => index += 1,
+				}
+			}
+
+			if let Some(element) = replacement {
+				self.append(Node::Element(element), depth);
+			}
+		}
+
+		Ok(())
+	}
+
+	fn to_xml(&self) -> Result<String> {
+		let mut writer = Writer::new(Vec::new());
+		write_element(&mut writer, self)?;
+		Ok(String::from_utf8(writer.into_inner())?)
+	}
+}
+
+// the elements `game` is written as, by name, with how each is written for comparing them
+fn game_fields(game: &Game) -> Result<BTreeMap<String, (Element, String)>> {
+	let mut fields = BTreeMap::new();
+	for child in game_element(game)?.children {
+		if let Node::Element(element) = child {
+			let name = String::from_utf8_lossy(element.start.name().as_ref()).to_string();
+			let xml = element.to_xml()?;
+			fields.insert(name, (element, xml));
+		}
+	}
+
+	Ok(fields)
+}
+
+fn game_element(game: &Game) -> Result<Element> {
+	let game = Game {
+		system: None,
+		..game.clone()
+	};
+
+	match parse_xml(&quick_xml::se::to_string_with_root("game", &game)?)?.pop() {
+		Some(Node::Element(element)) => Ok(element),
+		_ => Err(anyhow!("could not write {:?} as XML", game.path)),
+	}
+}
+
+fn parse_xml(xml: &str) -> Result<Vec<Node>> {
+	let mut reader = Reader::from_str(xml);
+	let mut top = Vec::new();
+	let mut open: Vec<Element> = Vec::new();
+
+	loop {
+		let node = match reader.read_event()? {
+			Event::Start(start) => {
+				open.push(Element {
+					start: start.into_owned(),
+					children: Vec::new(),
+					empty: false,
+				});
+				continue;
+			}
+			Event::End(_) => Node::Element(
+				open.pop()
+					.ok_or_else(|| anyhow!("unexpected end of element"))?,
+			),
+			Event::Empty(start) => Node::Element(Element {
+				start: start.into_owned(),
+				children: Vec::new(),
+				empty: true,
+			}),
+			Event::Eof => break,
+			event => Node::Event(event.into_owned()),
+		};
+
+		match open.last_mut() {
+			Some(parent) => parent.children.push(node),
+			None => top.push(node),
+		}
+	}
+
+	if !open.is_empty() {
+		return Err(anyhow!("unexpected end of file"));
+	}
+
+	Ok(top)
+}
+
+fn write_xml(nodes: &[Node]) -> Result<String> {
+	let mut writer = Writer::new(Vec::new());
+	write_nodes(&mut writer, nodes)?;
+	Ok(String::from_utf8(writer.into_inner())?)
+}
+
+fn write_nodes(writer: &mut Writer<Vec<u8>>, nodes: &[Node]) -> std::io::Result<()> {
+	for node in nodes {
+		match node {
+			Node::Event(event) => writer.write_event(event.clone())?,
+			Node::Element(element) => write_element(writer, element)?,
+		}
+	}
+
+	Ok(())
+}
+
+fn write_element(writer: &mut Writer<Vec<u8>>, element: &Element) -> std::io::Result<()> {
+	if element.empty && element.children.is_empty() {
+		return writer.write_event(Event::Empty(element.start.clone()));
+	}
+
+	writer.write_event(Event::Start(element.start.clone()))?;
+	write_nodes(writer, &element.children)?;
+	writer.write_event(Event::End(element.start.to_end()))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +362,8 @@ pub struct Game {
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub video: Option<PathBuf>,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub marquee: Option<PathBuf>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub rating: Option<String>,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub releasedate: Option<String>,
@@ -61,6 +381,73 @@ pub struct Game {
 	pub lastplayed: Option<String>,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub sortname: Option<String>,
+	/// The label of the alternative emulator to launch the game with, as in ES-DE.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub altemulator: Option<String>,
+	// flags are carried along so that rewriting a gamelist doesn't lose them
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub favorite: Option<bool>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub completed: Option<bool>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub kidgame: Option<bool>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub broken: Option<bool>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub hidden: Option<bool>,
+	/// The system a game in a virtual system comes from. Never read from a gamelist.
+	#[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+	pub system: Option<String>,
+	/// When the ROM was last written, read when the gamelist loads. Never in a gamelist.
+	#[serde(skip)]
+	pub modified: Option<SystemTime>,
+}
+
+impl Game {
+	pub fn is_hidden(&self) -> bool {
+		self.hidden.unwrap_or_default()
+	}
+
+	/// Counts a play that ended at `at`.
+	pub fn played(&mut self, at: SystemTime) {
+		self.playcount = Some(self.playcount.unwrap_or_default() + 1);
+		self.lastplayed = Some(es_timestamp(at));
+	}
+
+	/// The name used for ordering: `sortname` when present, otherwise `name`.
+	pub fn sort_name(&self) -> &str {
+		self.sortname
+			.as_deref()
+			.or(self.name.as_deref())
+			.unwrap_or_default()
+	}
+
+	/// The letter the game is listed under, ignoring leading articles. Names that don't start with
+	/// a letter are under `#`.
+	pub fn first_letter(&self) -> char {
+		match name_key(self.sort_name())
+			.chars()
+			.find(|x| x.is_alphanumeric())
+		{
+			Some(c) if c.is_alphabetic() => c.to_uppercase().next().unwrap_or(c),
+			_ => '#',
+		}
+	}
+}
+
+/// Formats a time the way gamelists store dates, `YYYYMMDDTHHMMSS`, in local time as ES-DE does.
+pub fn es_timestamp(time: SystemTime) -> String {
+	es_timestamp_in(time, &Local)
+}
+
+fn es_timestamp_in<Tz: TimeZone>(time: SystemTime, zone: &Tz) -> String
+where
+	Tz::Offset: std::fmt::Display,
+{
+	DateTime::<Utc>::from(time)
+		.with_timezone(zone)
+		.format("%Y%m%dT%H%M%S")
+		.to_string()
 }
 
 #[cfg(test)]
@@ -71,4 +458,108 @@ mod tests {
 	fn test_parse_xml() {
 		GameList::from_file("test-gamelist.xml".into()).unwrap();
 	}
+
+	#[test]
+	fn test_write_xml() {
+		let mut list = GameList::from_file("test-gamelist.xml".into()).unwrap();
+		list.game[0].hidden = Some(true);
+
+		let filename = std::env::temp_dir().join(format!(
+			"rawrcade-test-write-gamelist-{}.xml",
+			std::process::id()
+		));
+		list.to_file(&filename).unwrap();
+		let written = GameList::from_file(filename.clone()).unwrap();
+		std::fs::remove_file(filename).unwrap();
+
+		assert_eq!(written.game.len(), list.game.len());
+		assert_eq!(written.game[0].name, list.game[0].name);
+		assert!(written.game[0].is_hidden());
+		assert!(!written.game[1].is_hidden());
+	}
+
+	#[test]
+	fn test_save_games_keeps_unknown_elements() {
+		let xml = r#"<?xml version="1.0"?>
+<gameList>
+	<!-- scraped by ES-DE -->
+	<folder>
+		<path>./rpgs</path>
+		<name>RPGs</name>
+	</folder>
+	<game id="3212" source="ScreenScraper.fr">
+		<path>./axelay.sfc</path>
+		<name>Axelay</name>
+		<manual>./manuals/axelay.pdf</manual>
+		<playtime>5400</playtime>
+	</game>
+	<game>
+		<path>./contra.sfc</path>
+		<name>Contra III</name>
+	</game>
+	<game>
+		<path>./pilotwings.sfc</path>
+		<name>Pilotwings</name>
+		<fanart>./fanart/pilotwings.png</fanart>
+		<collectionsortname>Pilotwings 1</collectionsortname>
+	</game>
+</gameList>
+"#;
+		let filename = std::env::temp_dir().join(format!(
+			"rawrcade-test-save-games-{}.xml",
+			std::process::id()
+		));
+		std::fs::write(&filename, xml).unwrap();
+
+		let mut games = GameList::from_file(filename.clone()).unwrap().game;
+		games[0].hidden = Some(true);
+		games[2].name = Some("Pilotwings & Friends".into());
+		games.remove(1);
+		games.push(crate::Game {
+			path: Some("./f-zero.sfc".into()),
+			name: Some("F-Zero".into()),
+			..Default::default()
+		});
+
+		GameList::save_games(&filename, &games).unwrap();
+		let written = std::fs::read_to_string(&filename).unwrap();
+		let list = GameList::from_file(filename.clone()).unwrap();
+		std::fs::remove_file(filename).unwrap();
+
+		for kept in [
+			"<!-- scraped by ES-DE -->",
+			"<name>RPGs</name>",
+			r#"<game id="3212" source="ScreenScraper.fr">"#,
+			"<manual>./manuals/axelay.pdf</manual>",
+			"<playtime>5400</playtime>",
+			"<fanart>./fanart/pilotwings.png</fanart>",
+			"<collectionsortname>Pilotwings 1</collectionsortname>",
+			"\t\t<hidden>true</hidden>\n\t</game>",
+			"<name>Pilotwings &amp; Friends</name>",
+		] {
+			assert!(
+				written.contains(kept),
+				"{} is missing from\n{}",
+				kept,
+				written
+			);
+		}
+		assert!(!written.contains("Contra"));
+
+		let names: Vec<_> = list.game.iter().filter_map(|x| x.name.clone()).collect();
+		assert_eq!(names, ["Axelay", "Pilotwings & Friends", "F-Zero"]);
+		assert!(list.game[0].is_hidden());
+		assert_eq!(list.folder.len(), 1);
+	}
+
+	#[test]
+	fn test_es_timestamp() {
+		let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(951_827_696);
+		assert_eq!(
+			super::es_timestamp_in(time, &chrono::Utc),
+			"20000229T123456"
+		);
+		let cet = chrono::FixedOffset::east_opt(3600).unwrap();
+		assert_eq!(super::es_timestamp_in(time, &cet), "20000229T133456");
+	}
 }
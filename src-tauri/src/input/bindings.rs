@@ -0,0 +1,255 @@
+use crate::InputEvent;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	time::{Duration, Instant},
+};
+
+/// Controller buttons, independent of the input library. Names follow the SDL/gilrs layout, so
+/// `south` is A on an Xbox pad and cross on a PlayStation pad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadButton {
+	South,
+	East,
+	North,
+	West,
+	LeftTrigger,
+	LeftTrigger2,
+	RightTrigger,
+	RightTrigger2,
+	Select,
+	Start,
+	Mode,
+	LeftThumb,
+	RightThumb,
+	DPadUp,
+	DPadDown,
+	DPadLeft,
+	DPadRight,
+	RightStickUp,
+	RightStickDown,
+	RightStickLeft,
+	RightStickRight,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChordBinding {
+	pub buttons: Vec<PadButton>,
+	pub event: InputEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LongPressBinding {
+	pub button: PadButton,
+	pub event: InputEvent,
+}
+
+/// What each button does. Buttons that take part in a chord or long press fire their single-press
+/// event on release instead of on press, so holding them doesn't trigger both.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+	pub buttons: BTreeMap<PadButton, InputEvent>,
+	pub chords: Vec<ChordBinding>,
+	pub long_presses: Vec<LongPressBinding>,
+	pub long_press_ms: u64,
+}
+
+impl Default for InputBindings {
+	fn default() -> Self {
+		Self {
+			buttons: BTreeMap::from([
+				(PadButton::DPadUp, InputEvent::Up),
+				(PadButton::DPadDown, InputEvent::Down),
+				(PadButton::DPadLeft, InputEvent::Left),
+				(PadButton::DPadRight, InputEvent::Right),
+				(PadButton::Start, InputEvent::Menu),
+				(PadButton::Select, InputEvent::Search),
+				(PadButton::North, InputEvent::ToggleFavorite),
+				(PadButton::West, InputEvent::Details),
+				(PadButton::South, InputEvent::Ok),
+				(PadButton::East, InputEvent::Cancel),
+				(PadButton::LeftTrigger, InputEvent::PageUp),
+				(PadButton::RightTrigger, InputEvent::PageDown),
+				(PadButton::LeftTrigger2, InputEvent::First),
+				(PadButton::RightTrigger2, InputEvent::Last),
+				(PadButton::RightStickUp, InputEvent::PreviousLetter),
+				(PadButton::RightStickDown, InputEvent::NextLetter),
+			]),
+			chords: vec![
+				ChordBinding {
+					buttons: vec![PadButton::Select, PadButton::Start],
+					event: InputEvent::Quit,
+				},
+				ChordBinding {
+					buttons: vec![PadButton::LeftTrigger, PadButton::RightTrigger],
+					event: InputEvent::RandomGame,
+				},
+				ChordBinding {
+					buttons: vec![PadButton::LeftTrigger2, PadButton::RightTrigger2],
+					event: InputEvent::RandomGameAnywhere,
+				},
+			],
+			long_presses: vec![LongPressBinding {
+				button: PadButton::North,
+				event: InputEvent::Delete,
+			}],
+			long_press_ms: 800,
+		}
+	}
+}
+
+impl InputBindings {
+	fn is_deferred(&self, button: PadButton) -> bool {
+		self.chords.iter().any(|x| x.buttons.contains(&button))
+			|| self.long_presses.iter().any(|x| x.button == button)
+	}
+}
+
+/// Turns raw button presses and releases into [InputEvent]s according to [InputBindings].
+#[derive(Debug, Clone)]
+pub struct ButtonRecognizer {
+	bindings: InputBindings,
+	held: BTreeMap<PadButton, Instant>,
+	// buttons whose press was already used up by a chord or long press; releasing them does
+	// nothing.
+	consumed: BTreeSet<PadButton>,
+}
+
+impl ButtonRecognizer {
+	pub fn new(bindings: InputBindings) -> Self {
+		Self {
+			bindings,
+			held: Default::default(),
+			consumed: Default::default(),
+		}
+	}
+
+	pub fn press(&mut self, button: PadButton, now: Instant) -> Option<InputEvent> {
+		self.held.insert(button, now);
+
+		let chord = self.bindings.chords.iter().find(|x| {
+			x.buttons.contains(&button)
+				&& x.buttons
+					.iter()
+					.all(|b| self.held.contains_key(b) && !self.consumed.contains(b))
+		});
+
+		if let Some(chord) = chord {
+			let event = chord.event.clone();
+			self.consumed.extend(chord.buttons.iter().copied());
+			return Some(event);
+		}
+
+		if self.bindings.is_deferred(button) {
+			return None;
+		}
+
+		self.consumed.insert(button);
+		self.bindings.buttons.get(&button).cloned()
+	}
+
+	pub fn release(&mut self, button: PadButton) -> Option<InputEvent> {
+		self.held.remove(&button)?;
+
+		if self.consumed.remove(&button) {
+			return None;
+		}
+
+		self.bindings.buttons.get(&button).cloned()
+	}
+
+	/// For inputs that report a level instead of press and release, like stick directions.
+	pub fn set_held(&mut self, button: PadButton, held: bool, now: Instant) -> Option<InputEvent> {
+		match (held, self.held.contains_key(&button)) {
+			(true, false) => self.press(button, now),
+			(false, true) => self.release(button),
+			_ => None,
+		}
+	}
+
+	/// Fires any long presses that have been held long enough. Call this whenever
+	/// [Self::next_deadline] passes.
+	pub fn tick(&mut self, now: Instant) -> Vec<InputEvent> {
+		let threshold = Duration::from_millis(self.bindings.long_press_ms);
+		let mut events = Vec::new();
+
+		for binding in &self.bindings.long_presses {
+			if let Some(pressed) = self.held.get(&binding.button) {
+				if !self.consumed.contains(&binding.button) && now - *pressed >= threshold {
+					self.consumed.insert(binding.button);
+					events.push(binding.event.clone());
+				}
+			}
+		}
+
+		events
+	}
+
+	/// The next time a held button would turn into a long press, if any.
+	pub fn next_deadline(&self) -> Option<Instant> {
+		let threshold = Duration::from_millis(self.bindings.long_press_ms);
+
+		self.bindings
+			.long_presses
+			.iter()
+			.filter(|x| !self.consumed.contains(&x.button))
+			.filter_map(|x| self.held.get(&x.button))
+			.map(|x| *x + threshold)
+			.min()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn bindings() -> InputBindings {
+		InputBindings {
+			chords: vec![ChordBinding {
+				buttons: vec![PadButton::Select, PadButton::Start],
+				event: InputEvent::Quit,
+			}],
+			long_presses: vec![LongPressBinding {
+				button: PadButton::South,
+				event: InputEvent::Delete,
+			}],
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_chords_and_long_presses() {
+		let mut r = ButtonRecognizer::new(bindings());
+		let now = Instant::now();
+
+		// unrelated buttons fire immediately
+		assert_eq!(r.press(PadButton::DPadUp, now), Some(InputEvent::Up));
+		assert_eq!(r.release(PadButton::DPadUp), None);
+
+		// chord members wait for release, and fire nothing if the chord was completed
+		assert_eq!(r.press(PadButton::Start, now), None);
+		assert_eq!(r.press(PadButton::Select, now), Some(InputEvent::Quit));
+		assert_eq!(r.release(PadButton::Start), None);
+		assert_eq!(r.release(PadButton::Select), None);
+
+		assert_eq!(r.press(PadButton::Start, now), None);
+		assert_eq!(r.release(PadButton::Start), Some(InputEvent::Menu));
+
+		// short press
+		assert_eq!(r.press(PadButton::South, now), None);
+		assert!(r.tick(now + Duration::from_millis(100)).is_empty());
+		assert_eq!(r.release(PadButton::South), Some(InputEvent::Ok));
+
+		// long press
+		assert_eq!(r.press(PadButton::South, now), None);
+		assert_eq!(r.next_deadline(), Some(now + Duration::from_millis(800)));
+		assert_eq!(
+			r.tick(now + Duration::from_secs(1)),
+			vec![InputEvent::Delete]
+		);
+		assert_eq!(r.next_deadline(), None);
+		assert_eq!(r.release(PadButton::South), None);
+	}
+}
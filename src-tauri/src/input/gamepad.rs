@@ -0,0 +1,251 @@
+use super::{ButtonRecognizer, InputBackend, InputBindings, PadButton};
+use crate::{ControllerEvent, InputEvent};
+use anyhow::{Result, anyhow};
+use gilrs::{Axis, Button, Event as GamepadEvent, EventType as GamepadEventType, GamepadId, Gilrs};
+use std::{
+	collections::HashMap,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+	},
+	time::{Duration, Instant},
+};
+use tokio::sync::mpsc::Sender;
+
+/// Gamepad input through gilrs. Controllers coming and going are reported on a separate channel,
+/// and when `primary_only` is set only the first connected controller generates input.
+#[derive(Debug, Clone)]
+pub struct GamepadBackend {
+	controllers: Sender<ControllerEvent>,
+	primary_only: Arc<AtomicBool>,
+	bindings: InputBindings,
+}
+
+impl GamepadBackend {
+	pub fn new(
+		controllers: Sender<ControllerEvent>, primary_only: Arc<AtomicBool>,
+		bindings: InputBindings,
+	) -> Self {
+		Self {
+			controllers,
+			primary_only,
+			bindings,
+		}
+	}
+}
+
+fn pad_button(button: Button) -> Option<PadButton> {
+	Some(match button {
+		Button::South => PadButton::South,
+		Button::East => PadButton::East,
+		Button::North => PadButton::North,
+		Button::West => PadButton::West,
+		Button::LeftTrigger => PadButton::LeftTrigger,
+		Button::LeftTrigger2 => PadButton::LeftTrigger2,
+		Button::RightTrigger => PadButton::RightTrigger,
+		Button::RightTrigger2 => PadButton::RightTrigger2,
+		Button::Select => PadButton::Select,
+		Button::Start => PadButton::Start,
+		Button::Mode => PadButton::Mode,
+		Button::LeftThumb => PadButton::LeftThumb,
+		Button::RightThumb => PadButton::RightThumb,
+		Button::DPadUp => PadButton::DPadUp,
+		Button::DPadDown => PadButton::DPadDown,
+		Button::DPadLeft => PadButton::DPadLeft,
+		Button::DPadRight => PadButton::DPadRight,
+		_ => return None,
+	})
+}
+
+impl InputBackend for GamepadBackend {
+	fn name(&self) -> &'static str {
+		"gamepad"
+	}
+
+	fn start(self: Box<Self>, sender: Sender<InputEvent>) -> Result<()> {
+		// gilrs is not Send and blocks while waiting for events, so it lives on its own thread.
+		// Initialization happens there too; the result is reported back before returning.
+		let (init_send, init_recv) = std::sync::mpsc::channel();
+
+		std::thread::Builder::new()
+			.name("gamepad-input".into())
+			.spawn(move || match Gilrs::new() {
+				Ok(gilrs) => {
+					let _ = init_send.send(Ok(()));
+					handle_gamepad_input(gilrs, sender, *self);
+				}
+				Err(e) => {
+					let _ = init_send.send(Err(anyhow!("could not initialize gilrs: {}", e)));
+				}
+			})?;
+
+		init_recv.recv()?
+	}
+}
+
+fn handle_gamepad_input(mut gilrs: Gilrs, sender: Sender<InputEvent>, backend: GamepadBackend) {
+	let mut debounce: Option<Instant> = None;
+	let mut latest_axis: Option<(gilrs::Axis, f32)> = None;
+	// in order of connection; the first entry is the primary controller.
+	let mut connected: Vec<GamepadId> = Vec::new();
+	// each pad gets its own recognizer so chords can't span controllers.
+	let mut recognizers: HashMap<GamepadId, ButtonRecognizer> = HashMap::new();
+
+	// gilrs does not send Connected for pads that were present before it was initialized.
+	for (id, gamepad) in gilrs.gamepads() {
+		connected.push(id);
+		let _ = backend
+			.controllers
+			.blocking_send(ControllerEvent::Connected {
+				id: id.into(),
+				name: gamepad.name().to_string(),
+			});
+	}
+
+	'event_loop: loop {
+		// wake up in time to fire long presses while buttons are held
+		let timeout = recognizers
+			.values()
+			.filter_map(ButtonRecognizer::next_deadline)
+			.min()
+			.map(|x| x.saturating_duration_since(Instant::now()));
+
+		let next = gilrs.next_event_blocking(timeout);
+
+		let now = Instant::now();
+		for event in recognizers.values_mut().flat_map(|x| x.tick(now)) {
+			if sender.blocking_send(event).is_err() {
+				tracing::warn!("input channel closed, stopping gamepad input");
+				return;
+			}
+		}
+
+		if let Some(GamepadEvent { id, event, .. }) = next {
+			tracing::debug!("gamepad input event: {:?}", event);
+
+			match event {
+				GamepadEventType::Connected => {
+					if !connected.contains(&id) {
+						connected.push(id);
+					}
+
+					let _ = backend
+						.controllers
+						.blocking_send(ControllerEvent::Connected {
+							id: id.into(),
+							name: gilrs.gamepad(id).name().to_string(),
+						});
+					continue 'event_loop;
+				}
+				GamepadEventType::Disconnected => {
+					connected.retain(|x| *x != id);
+					recognizers.remove(&id);
+					let _ = backend
+						.controllers
+						.blocking_send(ControllerEvent::Disconnected { id: id.into() });
+					continue 'event_loop;
+				}
+				_ => {}
+			}
+
+			if backend.primary_only.load(Ordering::SeqCst) && connected.first() != Some(&id) {
+				continue 'event_loop;
+			}
+
+			let recognizer = recognizers
+				.entry(id)
+				.or_insert_with(|| ButtonRecognizer::new(backend.bindings.clone()));
+
+			let events: Vec<InputEvent> = match event {
+				GamepadEventType::AxisChanged(
+					x @ (Axis::RightStickX | Axis::RightStickY),
+					amp,
+					..,
+				) => {
+					// the right stick acts as four buttons, so it can be bound like one.
+					let (positive, negative) = match x {
+						Axis::RightStickX => {
+							(PadButton::RightStickRight, PadButton::RightStickLeft)
+						}
+						_ => (PadButton::RightStickUp, PadButton::RightStickDown),
+					};
+
+					[
+						recognizer.set_held(positive, amp > 0.5, now),
+						recognizer.set_held(negative, amp < -0.5, now),
+					]
+					.into_iter()
+					.flatten()
+					.collect()
+				}
+				GamepadEventType::AxisChanged(x, amp, ..) => {
+					if let Some(inner) = debounce {
+						if Instant::now() - inner < Duration::from_millis(200) {
+							continue 'event_loop;
+						} else {
+							debounce = None;
+						}
+					}
+
+					if let Some(inner) = latest_axis {
+						if inner.0 == x {
+							// NOTE: releasing the stick should not generate additional events. this
+							// fixes that.
+							if (inner.1 < 0.0 && amp > inner.1) || (inner.1 > 0.0 && amp < inner.1)
+							{
+								continue 'event_loop;
+							}
+						} else {
+							latest_axis = None;
+						}
+					}
+
+					let event = match x {
+						Axis::LeftStickY => {
+							if amp > 0.5 {
+								Some(InputEvent::Up)
+							} else if amp < -0.5 {
+								Some(InputEvent::Down)
+							} else {
+								None
+							}
+						}
+						Axis::LeftStickX => {
+							if amp > 0.5 {
+								Some(InputEvent::Right)
+							} else if amp < -0.5 {
+								Some(InputEvent::Left)
+							} else {
+								None
+							}
+						}
+						_ => None,
+					};
+
+					if event.is_some() {
+						latest_axis = Some((x, amp));
+						debounce = Some(Instant::now());
+					}
+
+					event.into_iter().collect()
+				}
+				GamepadEventType::ButtonPressed(x, ..) => pad_button(x)
+					.and_then(|button| recognizer.press(button, now))
+					.into_iter()
+					.collect(),
+				GamepadEventType::ButtonReleased(x, ..) => pad_button(x)
+					.and_then(|button| recognizer.release(button))
+					.into_iter()
+					.collect(),
+				_ => Vec::new(),
+			};
+
+			for event in events {
+				if sender.blocking_send(event).is_err() {
+					tracing::warn!("input channel closed, stopping gamepad input");
+					return;
+				}
+			}
+		}
+	}
+}
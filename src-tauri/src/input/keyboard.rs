@@ -0,0 +1,87 @@
+use super::InputBackend;
+use crate::InputEvent;
+use anyhow::Result;
+use tokio::sync::mpsc::{Receiver, Sender, channel};
+
+// DOM key names and what they do
+const KEYS: &[(&str, InputEvent)] = &[
+	("ArrowUp", InputEvent::Up),
+	("ArrowDown", InputEvent::Down),
+	("ArrowLeft", InputEvent::Left),
+	("ArrowRight", InputEvent::Right),
+	("Enter", InputEvent::Ok),
+	("Escape", InputEvent::Cancel),
+	("Backspace", InputEvent::Cancel),
+	("Tab", InputEvent::Menu),
+	("PageUp", InputEvent::PageUp),
+	("PageDown", InputEvent::PageDown),
+	("Home", InputEvent::First),
+	("End", InputEvent::Last),
+	("Delete", InputEvent::Delete),
+	("/", InputEvent::Search),
+	("f", InputEvent::ToggleFavorite),
+	("F", InputEvent::ToggleFavorite),
+	("i", InputEvent::Details),
+	("I", InputEvent::Details),
+	("r", InputEvent::RandomGame),
+	("R", InputEvent::RandomGameAnywhere),
+	("q", InputEvent::Quit),
+	("Q", InputEvent::Quit),
+];
+
+/// Handle given to the `keyboard_input` command; forwards key names from the webview to the
+/// [KeyboardBackend].
+#[derive(Debug, Clone)]
+pub struct KeyboardInput(Sender<String>);
+
+impl KeyboardInput {
+	pub async fn send(&self, key: String) {
+		let _ = self.0.send(key).await;
+	}
+}
+
+/// Keyboard input. The webview owns keyboard focus, so key presses arrive as DOM `KeyboardEvent.key`
+/// names through [KeyboardInput] and are translated here.
+#[derive(Debug)]
+pub struct KeyboardBackend {
+	keys: Receiver<String>,
+}
+
+impl KeyboardBackend {
+	pub fn new() -> (Self, KeyboardInput) {
+		let (s, r) = channel(100);
+		(Self { keys: r }, KeyboardInput(s))
+	}
+
+	pub fn map_key(key: &str) -> Option<InputEvent> {
+		KEYS.iter()
+			.find(|(name, _)| *name == key)
+			.map(|(_, event)| event.clone())
+	}
+
+	/// The key names that do something, so the webview only keeps those from the browser.
+	pub fn keys() -> Vec<String> {
+		KEYS.iter().map(|(name, _)| name.to_string()).collect()
+	}
+}
+
+impl InputBackend for KeyboardBackend {
+	fn name(&self) -> &'static str {
+		"keyboard"
+	}
+
+	fn start(mut self: Box<Self>, sender: Sender<InputEvent>) -> Result<()> {
+		tauri::async_runtime::spawn(async move {
+			while let Some(key) = self.keys.recv().await {
+				if let Some(event) = Self::map_key(&key) {
+					tracing::debug!("keyboard input event: {} -> {:?}", key, event);
+					if sender.send(event).await.is_err() {
+						return;
+					}
+				}
+			}
+		});
+
+		Ok(())
+	}
+}
@@ -0,0 +1,47 @@
+use crate::InputEvent;
+use anyhow::Result;
+use tokio::sync::mpsc::Sender;
+
+mod bindings;
+mod gamepad;
+mod keyboard;
+mod scripted;
+pub use self::bindings::*;
+pub use self::gamepad::*;
+pub use self::keyboard::*;
+pub use self::scripted::*;
+
+/// A source of [InputEvent]s. Backends are started once at boot and feed the application's input
+/// channel for the rest of its lifetime.
+pub trait InputBackend: Send {
+	fn name(&self) -> &'static str;
+
+	/// Starts the backend. Errors mean the backend is unavailable on this machine; the backend
+	/// must not have spawned anything in that case.
+	fn start(self: Box<Self>, sender: Sender<InputEvent>) -> Result<()>;
+}
+
+/// Starts every backend, logging the ones that are unavailable instead of failing. Returns the
+/// number of backends that were started.
+pub fn start_input_backends(
+	backends: Vec<Box<dyn InputBackend>>, sender: &Sender<InputEvent>,
+) -> usize {
+	let mut started = 0;
+
+	for backend in backends {
+		let name = backend.name();
+		match backend.start(sender.clone()) {
+			Ok(()) => {
+				tracing::info!("started input backend: {}", name);
+				started += 1;
+			}
+			Err(e) => tracing::warn!("input backend {} is unavailable: {}", name, e),
+		}
+	}
+
+	if started == 0 {
+		tracing::error!("no input backends could be started");
+	}
+
+	started
+}
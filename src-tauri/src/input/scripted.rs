@@ -0,0 +1,52 @@
+use super::InputBackend;
+use crate::InputEvent;
+use anyhow::Result;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+
+#[derive(Debug, Clone)]
+pub enum ScriptStep {
+	Press(InputEvent),
+	Wait(Duration),
+}
+
+/// Plays back a fixed script of input. Used to drive the state machine in tests and for demos
+/// without any hardware attached.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedBackend {
+	script: Vec<ScriptStep>,
+}
+
+impl ScriptedBackend {
+	pub fn new(script: Vec<ScriptStep>) -> Self {
+		Self { script }
+	}
+
+	/// A script that presses each event in order with no delay in between.
+	pub fn presses(events: impl IntoIterator<Item = InputEvent>) -> Self {
+		Self::new(events.into_iter().map(ScriptStep::Press).collect())
+	}
+}
+
+impl InputBackend for ScriptedBackend {
+	fn name(&self) -> &'static str {
+		"scripted"
+	}
+
+	fn start(self: Box<Self>, sender: Sender<InputEvent>) -> Result<()> {
+		tauri::async_runtime::spawn(async move {
+			for step in self.script {
+				match step {
+					ScriptStep::Press(event) => {
+						if sender.send(event).await.is_err() {
+							return;
+						}
+					}
+					ScriptStep::Wait(duration) => tokio::time::sleep(duration).await,
+				}
+			}
+		});
+
+		Ok(())
+	}
+}
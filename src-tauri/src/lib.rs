@@ -1,37 +1,116 @@
+use clap::Parser;
 use std::sync::OnceLock;
 use tauri::{AppHandle, Manager};
+use tracing_subscriber::{Registry, filter::LevelFilter, prelude::*, reload};
 
 mod app;
+mod cli;
+mod collections;
 mod command;
 mod config;
+mod es_theme;
+mod filter;
 mod gamelist;
-mod gamepad;
+mod input;
+mod search;
+mod sort;
+mod state;
 mod systems;
+mod themes;
+mod watch;
 
 pub use self::app::*;
+pub use self::cli::*;
+pub use self::collections::*;
 pub use self::command::*;
 pub use self::config::*;
+pub use self::es_theme::*;
+pub use self::filter::*;
 pub use self::gamelist::*;
-pub(crate) use self::gamepad::*;
+pub use self::input::*;
+pub use self::search::*;
+pub use self::sort::*;
+pub use self::state::*;
 pub use self::systems::*;
+pub use self::themes::*;
+pub use self::watch::*;
 
 pub static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
 
+/// Changes how much is logged while running, for the Log Level setting.
+pub(crate) static LOG_LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub async fn run() {
-	let appdata = App::new(None).expect("could not initialize application");
+	let cli = Cli::parse();
+
+	// logging starts before the configuration is read so that problems reading it are logged,
+	// at the default level until the configured one is known
+	let level: tracing::Level = cli
+		.log_level
+		.clone()
+		.unwrap_or(Config::default().log_level)
+		.into();
+	let (filter, handle) = reload::Layer::new(LevelFilter::from(level));
+	tracing_subscriber::registry()
+		.with(filter)
+		.with(tracing_subscriber::fmt::layer())
+		.init();
+	let _ = LOG_LEVEL.set(handle);
+
+	let appdata = match App::new(&cli) {
+		Ok(appdata) => appdata,
+		Err(e) => {
+			eprintln!("rawrcade: {}", e);
+			std::process::exit(1);
+		}
+	};
+
+	if cli.log_level.is_none() {
+		let level: tracing::Level = appdata.config.lock().await.log_level.clone().into();
+		if let Some(Err(e)) = LOG_LEVEL.get().map(|x| x.reload(LevelFilter::from(level))) {
+			tracing::error!("could not change the log level: {}", e);
+		}
+	}
+
+	if cli.list_systems {
+		for system in appdata.all_systems.lock().await.system.iter() {
+			if !system.is_virtual {
+				println!(
+					"{}\t{}\t{} games",
+					system.name,
+					system.fullname,
+					system.games.len()
+				);
+			}
+		}
+		return;
+	}
+
 	let inner = appdata.clone();
 	let config = appdata.config.clone();
+	let windowed = cli.windowed;
 
-	tracing_subscriber::fmt()
-		.with_max_level(Into::<tracing::Level>::into(
-			appdata.config.lock().await.log_level.clone(),
-		))
-		.init();
+	let (keyboard, keyboard_handle) = KeyboardBackend::new();
+	let bindings = appdata.config.lock().await.bindings.clone();
 
-	let sender = appdata.input_send.clone();
+	// a missing backend (no gamepad support, etc) is logged, not fatal.
+	start_input_backends(
+		vec![
+			Box::new(GamepadBackend::new(
+				appdata.controller_send.clone(),
+				appdata.primary_controller_only.clone(),
+				bindings,
+			)),
+			Box::new(keyboard),
+		],
+		&appdata.input_send,
+	);
 
-	tauri::async_runtime::spawn(async move { handle_gamepad_input(sender).await });
+	appdata
+		.watcher
+		.start(appdata.data_dir().to_path_buf(), appdata.event_send.clone())
+		.await;
 
 	tauri::async_runtime::spawn(async move { inner.event_loop().await });
 
@@ -42,7 +121,7 @@ pub async fn run() {
 			if let Some(app_handle) = APP_HANDLE.get() {
 				if let Some(window) = app_handle.get_window("main") {
 					window
-						.set_fullscreen(config.lock().await.start_fullscreen)
+						.set_fullscreen(!windowed && config.lock().await.start_fullscreen)
 						.unwrap();
 					return;
 				}
@@ -55,18 +134,26 @@ pub async fn run() {
 	let builder = tauri::Builder::default()
 		.setup(|app| {
 			app.manage(appdata);
+			app.manage(keyboard_handle);
 			Ok(())
 		})
 		.plugin(tauri_plugin_opener::init())
 		.invoke_handler(tauri::generate_handler![
 			all_systems,
+			controllers_menu,
+			current_menu,
 			current_orientation,
 			current_asset,
+			current_details,
+			current_editor,
 			current_text,
-			menu,
-			setting_types,
+			keyboard_input,
+			keyboard_keys,
+			settings,
 			settings_menu,
-			setting_values,
+			active_theme,
+			theme_layout,
+			setup_status,
 		])
 		.build(context)
 		.unwrap();
@@ -0,0 +1,122 @@
+use crate::{Game, SystemList, name_key, natural_cmp};
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+/// The name of the virtual system holding search results.
+pub const SEARCH_SYSTEM: &str = "search";
+
+/// Lowercases `text` and strips accents and punctuation, so "Pokémon: Red" and "pokemon red"
+/// compare equal.
+pub fn fold(text: &str) -> String {
+	let folded: String = text
+		.nfd()
+		.filter(|x| !is_combining_mark(*x))
+		.flat_map(char::to_lowercase)
+		.map(|x| if x.is_alphanumeric() { x } else { ' ' })
+		.collect();
+
+	folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// How well a game matches a folded query; higher is better and `None` is no match. Names that
+/// contain the query beat names that only contain its letters in order, which beat descriptions
+/// that contain every word of it.
+pub fn score(query: &str, game: &Game) -> Option<u32> {
+	if query.is_empty() {
+		return None;
+	}
+
+	let name = fold(game.name.as_deref().unwrap_or_default());
+
+	if let Some(position) = name.find(query) {
+		let boundary = position == 0 || name[..position].ends_with(' ');
+		return Some(
+			3000 + if position == 0 { 500 } else { 0 } + if boundary { 250 } else { 0 }
+				- (name.len() - query.len()).min(250) as u32,
+		);
+	}
+
+	if let Some(gaps) = subsequence_gaps(query, &name) {
+		return Some(2000 - gaps.min(1000) as u32);
+	}
+
+	let desc = fold(game.desc.as_deref().unwrap_or_default());
+	query
+		.split(' ')
+		.all(|word| desc.split(' ').any(|x| x.starts_with(word)))
+		.then_some(1000)
+}
+
+// the letters of `query` skipped over in `text` when finding them in order, ignoring spaces
+fn subsequence_gaps(query: &str, text: &str) -> Option<usize> {
+	let mut text = text.chars().filter(|x| *x != ' ');
+	let mut gaps = 0;
+
+	for c in query.chars().filter(|x| *x != ' ') {
+		loop {
+			let next = text.next()?;
+			if next == c {
+				break;
+			}
+			gaps += 1;
+		}
+	}
+
+	Some(gaps)
+}
+
+/// Every visible game of every real system matching `query`, best first. Each result carries
+/// the name of the system it comes from in [Game::system].
+pub fn search(systems: &SystemList, query: &str) -> Vec<Game> {
+	let query = fold(query);
+
+	let mut results: Vec<(u32, Game)> = systems
+		.system
+		.iter()
+		.filter(|x| !x.is_virtual)
+		.flat_map(|system| {
+			system
+				.games
+				.iter()
+				.filter(|x| systems.shows(x))
+				.filter_map(|game| {
+					let score = score(&query, game)?;
+					let mut game = game.clone();
+					game.system = Some(system.name.clone());
+					Some((score, game))
+				})
+		})
+		.collect();
+
+	results.sort_by(|(a_score, a), (b_score, b)| {
+		b_score
+			.cmp(a_score)
+			.then_with(|| natural_cmp(&name_key(a.sort_name()), &name_key(b.sort_name())))
+	});
+
+	results.into_iter().map(|(_, game)| game).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_fuzzy_accent_insensitive_score() {
+		let game = |name: &str, desc: &str| Game {
+			name: Some(name.to_string()),
+			desc: Some(desc.to_string()),
+			..Default::default()
+		};
+
+		let pokemon = game("Pokémon: Red Version", "Catch them all.");
+		let query = fold("pokemon red");
+		assert_eq!(query, "pokemon red");
+
+		let exact = score(&query, &pokemon).unwrap();
+		let fuzzy = score(&fold("pkmn rd"), &pokemon).unwrap();
+		let described = score(&fold("catch"), &pokemon).unwrap();
+		assert!(exact > fuzzy && fuzzy > described);
+
+		assert_eq!(score(&fold("zelda"), &pokemon), None);
+	}
+}
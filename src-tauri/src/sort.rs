@@ -0,0 +1,184 @@
+use crate::Game;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+// leading words ignored when ordering by name
+const ARTICLES: &[&str] = &["the ", "a ", "an "];
+
+/// How a system's games are ordered. Ties are broken by name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+	#[default]
+	Name,
+	/// Oldest first.
+	ReleaseDate,
+	/// Highest first.
+	Rating,
+	/// Most played first.
+	PlayCount,
+	/// Most recently played first.
+	LastPlayed,
+	Developer,
+	Genre,
+}
+
+impl SortMode {
+	pub fn all() -> Vec<Self> {
+		vec![
+			Self::Name,
+			Self::ReleaseDate,
+			Self::Rating,
+			Self::PlayCount,
+			Self::LastPlayed,
+			Self::Developer,
+			Self::Genre,
+		]
+	}
+
+	pub fn compare(&self, a: &Game, b: &Game) -> Ordering {
+		// games missing the field go last whichever way the field is ordered
+		fn present<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+			match (a, b) {
+				(Some(a), Some(b)) if descending => b.cmp(&a),
+				(Some(a), Some(b)) => a.cmp(&b),
+				(Some(_), None) => Ordering::Less,
+				(None, Some(_)) => Ordering::Greater,
+				(None, None) => Ordering::Equal,
+			}
+		}
+
+		let text = |x: &Option<String>| {
+			x.as_deref()
+				.map(str::trim)
+				.filter(|x| !x.is_empty())
+				.map(str::to_lowercase)
+		};
+
+		let rating = |x: &Game| {
+			x.rating
+				.as_deref()
+				.and_then(|x| x.trim().parse::<f64>().ok())
+				.map(|x| (x * 1000.0) as i64)
+		};
+
+		let order = match self {
+			Self::Name => Ordering::Equal,
+			// ES dates are `YYYYMMDDTHHMMSS`, which order correctly as text
+			Self::ReleaseDate => present(text(&a.releasedate), text(&b.releasedate), false),
+			Self::Rating => present(rating(a), rating(b), true),
+			Self::PlayCount => present(a.playcount, b.playcount, true),
+			Self::LastPlayed => present(text(&a.lastplayed), text(&b.lastplayed), true),
+			Self::Developer => present(text(&a.developer), text(&b.developer), false),
+			Self::Genre => present(text(&a.genre), text(&b.genre), false),
+		};
+
+		order.then_with(|| natural_cmp(&name_key(a.sort_name()), &name_key(b.sort_name())))
+	}
+
+	pub fn sort(&self, games: &mut [Game]) {
+		games.sort_by(|a, b| self.compare(a, b));
+	}
+}
+
+impl std::fmt::Display for SortMode {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			SortMode::Name => "Name",
+			SortMode::ReleaseDate => "Release Date",
+			SortMode::Rating => "Rating",
+			SortMode::PlayCount => "Times Played",
+			SortMode::LastPlayed => "Last Played",
+			SortMode::Developer => "Developer",
+			SortMode::Genre => "Genre",
+		})
+	}
+}
+
+/// A name lowercased and without a leading article, so "The Legend of Zelda" sorts under L.
+pub fn name_key(name: &str) -> String {
+	let name = name.trim().to_lowercase();
+
+	ARTICLES
+		.iter()
+		.find_map(|x| name.strip_prefix(x))
+		.map(|x| x.trim_start().to_string())
+		.unwrap_or(name)
+}
+
+/// Compares strings with runs of digits ordered by value, so "Game 2" comes before "Game 10".
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+	let mut a = a.chars().peekable();
+	let mut b = b.chars().peekable();
+
+	loop {
+		match (a.peek().copied(), b.peek().copied()) {
+			(None, None) => return Ordering::Equal,
+			(None, Some(_)) => return Ordering::Less,
+			(Some(_), None) => return Ordering::Greater,
+			(Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+				let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+					let mut digits = String::new();
+					while let Some(c) = chars.next_if(char::is_ascii_digit) {
+						digits.push(c);
+					}
+					digits
+				};
+
+				let x = take_number(&mut a);
+				let y = take_number(&mut b);
+				let (xt, yt) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+
+				let order = xt.len().cmp(&yt.len()).then_with(|| xt.cmp(yt));
+				if order != Ordering::Equal {
+					return order;
+				}
+			}
+			(Some(x), Some(y)) => {
+				if x != y {
+					return x.cmp(&y);
+				}
+
+				a.next();
+				b.next();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn game(name: &str, rating: Option<&str>) -> Game {
+		Game {
+			name: Some(name.to_string()),
+			rating: rating.map(ToString::to_string),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_sort_modes() {
+		let mut games = vec![
+			game("Game 10", None),
+			game("The Legend of Zelda", Some("0.9")),
+			game("Game 2", Some("0.5")),
+			game("an Adventure", Some("0.9")),
+		];
+
+		SortMode::Name.sort(&mut games);
+		let names: Vec<&str> = games.iter().map(Game::sort_name).collect();
+		assert_eq!(
+			names,
+			vec!["an Adventure", "Game 2", "Game 10", "The Legend of Zelda"]
+		);
+
+		SortMode::Rating.sort(&mut games);
+		let names: Vec<&str> = games.iter().map(Game::sort_name).collect();
+		assert_eq!(
+			names,
+			vec!["an Adventure", "The Legend of Zelda", "Game 2", "Game 10"]
+		);
+	}
+}
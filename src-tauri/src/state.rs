@@ -0,0 +1,135 @@
+use crate::{Orientation, SystemList, View};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+pub const DEFAULT_STATE_FILENAME: &str = "rawrcade/state.json";
+
+/// Where the player was, kept across restarts. Systems are remembered by name and games by path,
+/// so positions survive systems and games being added or removed in between.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NavigationState {
+	pub system: Option<String>,
+	/// The selected game of every system visited, by system name.
+	pub games: BTreeMap<String, PathBuf>,
+	pub views: Vec<View>,
+}
+
+impl NavigationState {
+	pub fn from_file(filename: &Path) -> Result<Self> {
+		let f = std::fs::OpenOptions::new().read(true).open(filename)?;
+
+		Ok(serde_json::from_reader(f)?)
+	}
+
+	pub fn to_file(&self, filename: &Path) -> Result<()> {
+		if let Some(parent) = filename.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+
+		let mut tmp = filename.to_path_buf().into_os_string();
+		tmp.push(".tmp");
+
+		std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+		std::fs::rename(&tmp, filename)?;
+
+		Ok(())
+	}
+
+	/// Records the current system and game.
+	pub fn remember(&mut self, systems: &SystemList, orientation: &Orientation) {
+		let Some(system) = systems.system.get(orientation.system_index) else {
+			return;
+		};
+
+		self.system = Some(system.name.clone());
+		self.views = orientation.views.clone();
+
+		if let Some(path) = system
+			.gamelist
+			.get(orientation.gamelist_index)
+			.and_then(|x| x.path.clone())
+		{
+			self.games.insert(system.name.clone(), path);
+		}
+	}
+
+	/// The index of the game last selected in a system, or 0 if it isn't in the list anymore.
+	pub fn game_index(&self, systems: &SystemList, system_index: usize) -> usize {
+		let Some(system) = systems.system.get(system_index) else {
+			return 0;
+		};
+
+		self.games
+			.get(&system.name)
+			.and_then(|path| {
+				system
+					.gamelist
+					.iter()
+					.position(|x| x.path.as_ref() == Some(path))
+			})
+			.unwrap_or_default()
+	}
+
+	/// Where to start, falling back to the first system and game for anything that is gone.
+	pub fn restore(&self, systems: &SystemList) -> Orientation {
+		let system_index = self
+			.system
+			.as_ref()
+			.and_then(|name| systems.system.iter().position(|x| x.name == *name))
+			.unwrap_or_default();
+
+		Orientation {
+			system_index,
+			gamelist_index: self.game_index(systems, system_index),
+			views: self.views.clone(),
+			..Default::default()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_restore_by_name_and_path() {
+		let mut systems = SystemList::from_file("test-systems.xml".into()).unwrap();
+		let games = crate::GameList::from_file("test-gamelist.xml".into())
+			.unwrap()
+			.game;
+		for system in &mut systems.system {
+			system.games = games.clone();
+			system.refresh();
+		}
+
+		let mut state = NavigationState::default();
+		state.remember(
+			&systems,
+			&Orientation {
+				system_index: 1,
+				gamelist_index: 2,
+				..Default::default()
+			},
+		);
+
+		// a system added at the front and a game removed before the selected one move both
+		// indices down
+		let mut changed = systems.clone();
+		changed.system.insert(0, changed.system[0].clone());
+		changed.system[0].name = "new".to_string();
+		changed.system[2].gamelist.remove(0);
+
+		let orientation = state.restore(&changed);
+		assert_eq!(orientation.system_index, 2);
+		assert_eq!(orientation.gamelist_index, 1);
+
+		// anything gone falls back to the start
+		changed.system.remove(2);
+		assert_eq!(state.restore(&changed), Orientation::default());
+	}
+}
@@ -1,16 +1,149 @@
 use anyhow::Result;
+use rand::{Rng, seq::IndexedRandom};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::Game;
+use crate::{Config, CustomCollection, Game, GameFilter, GameList, SortMode, UiMode};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SystemList {
 	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub system: Vec<System>,
+	/// The player's collections, shown as virtual systems.
+	#[serde(skip)]
+	pub custom: Vec<CustomCollection>,
+	/// Leaves every game not marked as `kidgame` out of collections and search, in
+	/// [UiMode::Kid].
+	#[serde(skip)]
+	pub kid_games_only: bool,
 }
 
 impl SystemList {
+	/// The number of games shown for a system; 0 for a system that doesn't exist.
+	pub fn gamelist_len(&self, system_index: usize) -> usize {
+		self.system
+			.get(system_index)
+			.map_or(0, |x| x.gamelist.len())
+	}
+
+	/// Orders and filters every system's games the way the configuration says, rebuilding only
+	/// the systems whose settings changed. Returns whether any did.
+	pub fn apply_config(&mut self, config: &Config) -> bool {
+		let mut changed = false;
+		self.kid_games_only = config.ui_mode == UiMode::Kid;
+
+		for system in &mut self.system {
+			// virtual systems keep the order they were built in unless told otherwise
+			let sort = match config.sort.get(&system.name) {
+				Some(sort) => Some(*sort),
+				None if system.is_virtual => None,
+				None => Some(SortMode::default()),
+			};
+			let filter = GameFilter {
+				kid_games_only: self.kid_games_only,
+				..config.filter(&system.name)
+			};
+			if system.sort != sort || system.filter != filter {
+				system.sort = sort;
+				system.filter = filter;
+				system.refresh();
+				changed = true;
+			}
+		}
+
+		changed
+	}
+
+	/// Replaces the virtual system with the same name, or adds it in front of the real ones.
+	/// Returns its index.
+	pub fn set_virtual(&mut self, system: System) -> usize {
+		let name = system.name.clone();
+
+		match self
+			.system
+			.iter()
+			.position(|x| x.is_virtual && x.name == name)
+		{
+			Some(index) => self.system[index] = system,
+			None => self.system.insert(0, system),
+		}
+
+		self.order_virtual();
+		self.system
+			.iter()
+			.position(|x| x.is_virtual && x.name == name)
+			.unwrap_or_default()
+	}
+
+	/// Changes a game of a real system along with its copies in virtual systems, which keep their
+	/// order. Returns the game's system, to be saved, or `None` if there is no such system.
+	pub fn update_game(
+		&mut self, system: &str, path: &Path, f: impl Fn(&mut Game),
+	) -> Option<&mut System> {
+		for other in self.system.iter_mut().filter(|x| x.is_virtual) {
+			for game in other.games.iter_mut().chain(other.gamelist.iter_mut()) {
+				if game.system.as_deref() == Some(system) && game.path.as_deref() == Some(path) {
+					f(game);
+				}
+			}
+		}
+
+		let origin = self
+			.system
+			.iter_mut()
+			.find(|x| !x.is_virtual && x.name == system)?;
+
+		for game in origin
+			.games
+			.iter_mut()
+			.filter(|x| x.path.as_deref() == Some(path))
+		{
+			f(game);
+		}
+
+		origin.refresh();
+		Some(origin)
+	}
+
+	/// The system a game was launched from, which for games in a virtual system is the one they
+	/// come from.
+	pub fn origin(&self, system_index: usize, game: &Game) -> Option<&System> {
+		match &game.system {
+			Some(name) => self
+				.system
+				.iter()
+				.find(|x| !x.is_virtual && x.name == *name),
+			None => self.system.get(system_index),
+		}
+	}
+
+	/// Whether `game` can show up outside of its own system, in collections and search.
+	pub fn shows(&self, game: &Game) -> bool {
+		!game.is_hidden() && (!self.kid_games_only || game.kidgame.unwrap_or_default())
+	}
+
+	/// A random visible game, as its system's index and its index in that system's gamelist.
+	/// With `system` it comes from that system, otherwise from any real one, every game being
+	/// as likely as any other. `current` is only picked when there is nothing else.
+	pub fn random_game(
+		&self, system: Option<usize>, current: (usize, usize), rng: &mut impl Rng,
+	) -> Option<(usize, usize)> {
+		let games: Vec<(usize, usize)> = self
+			.system
+			.iter()
+			.enumerate()
+			.filter(|(index, x)| system.map_or(!x.is_virtual, |system| *index == system))
+			.flat_map(|(index, x)| (0..x.gamelist.len()).map(move |game| (index, game)))
+			.collect();
+
+		let others: Vec<(usize, usize)> = games.iter().copied().filter(|x| *x != current).collect();
+
+		match others.is_empty() {
+			true => games.first().copied(),
+			false => others.choose(rng).copied(),
+		}
+	}
+
 	pub fn from_file(filename: PathBuf) -> Result<Self> {
 		Ok(quick_xml::de::from_reader(std::io::BufReader::new(
 			std::fs::OpenOptions::new().read(true).open(filename)?,
@@ -18,26 +151,303 @@ impl SystemList {
 	}
 }
 
+/// One way of launching a system's games. ES-DE lists alternative emulators as more commands,
+/// each with a `label`; the first one is the default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SystemCommand {
+	#[serde(rename = "@label", default, skip_serializing_if = "Option::is_none")]
+	pub label: Option<String>,
+	#[serde(rename = "$text", default)]
+	pub value: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct System {
 	pub name: String,
 	pub fullname: String,
 	pub path: PathBuf,
 	pub extension: String,
-	pub command: String,
+	#[serde(default)]
+	pub command: Vec<SystemCommand>,
 	pub platform: String,
+	/// The system's folder in EmulationStation themes, when it differs from [Self::name].
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub theme: Option<String>,
+	/// The games shown to the user; see [Self::refresh].
 	#[serde(skip_deserializing)]
 	pub gamelist: Vec<Game>,
+	/// Every game in the system's gamelist file, including hidden ones.
+	#[serde(skip)]
+	pub games: Vec<Game>,
+	#[serde(skip)]
+	pub gamelist_path: Option<PathBuf>,
+	/// How the gamelist is ordered; `None` keeps the order of `games`.
+	#[serde(skip)]
+	pub sort: Option<SortMode>,
+	#[serde(skip)]
+	pub filter: GameFilter,
+	/// Built from the games of other systems, like search results, rather than read from
+	/// `es_systems.cfg`.
+	#[serde(skip_deserializing)]
+	pub is_virtual: bool,
 }
 
 impl System {
-	pub fn get_command(&self, path: PathBuf) -> String {
+	/// A system listing `games` of other systems, in the order given.
+	pub fn new_virtual(name: &str, fullname: &str, games: Vec<Game>) -> Self {
+		Self {
+			name: name.to_string(),
+			fullname: fullname.to_string(),
+			path: PathBuf::new(),
+			extension: String::new(),
+			command: Vec::new(),
+			platform: name.to_string(),
+			theme: None,
+			gamelist: games.clone(),
+			games,
+			gamelist_path: None,
+			sort: Default::default(),
+			filter: Default::default(),
+			is_virtual: true,
+		}
+	}
+
+	/// The absolute path of a game's ROM.
+	pub fn rom_path(&self, game: &Game) -> Option<PathBuf> {
+		self.resolve(game.path.as_ref()?)
+	}
+
+	/// The absolute path of a file named in the gamelist, like a ROM or an image. Gamelists may
+	/// give them relative to the system's directory, which may itself start with `~`.
+	pub fn resolve(&self, path: &Path) -> Option<PathBuf> {
+		if path.is_absolute() {
+			return Some(path.to_path_buf());
+		}
+
+		let dir = match self.path.strip_prefix("~") {
+			Ok(rest) => dirs::home_dir()?.join(rest),
+			Err(_) => self.path.clone(),
+		};
+
+		Some(dir.join(path.strip_prefix(".").unwrap_or(path)))
+	}
+
+	/// The system's folder in EmulationStation themes.
+	pub fn theme_folder(&self) -> &str {
+		self.theme.as_deref().unwrap_or(&self.name)
+	}
+
+	/// Rebuilds the visible gamelist from the full list of games, filtered and in order.
+	/// Reads when each game's ROM was last written, for the "Last Added" collection.
+	pub fn read_rom_times(&mut self) {
+		for i in 0..self.games.len() {
+			self.games[i].modified = self
+				.rom_path(&self.games[i])
+				.and_then(|x| std::fs::metadata(x).ok()?.modified().ok());
+		}
+	}
+
+	pub fn refresh(&mut self) {
+		self.gamelist = self
+			.games
+			.iter()
+			.filter(|x| !x.is_hidden() && self.filter.matches(x))
+			.cloned()
+			.collect();
+
+		if let Some(sort) = self.sort {
+			sort.sort(&mut self.gamelist);
+		}
+	}
+
+	/// The index of the first game under the next (or previous) letter, wrapping around at either
+	/// end of the list.
+	pub fn letter_jump(&self, index: usize, forward: bool) -> usize {
+		let letters: Vec<char> = self.gamelist.iter().map(Game::first_letter).collect();
+		if letters.is_empty() {
+			return 0;
+		}
+
+		let index = index.min(letters.len() - 1);
+		let run_start = |mut i: usize| {
+			while i > 0 && letters[i - 1] == letters[i] {
+				i -= 1;
+			}
+			i
+		};
+
+		if forward {
+			letters[index..]
+				.iter()
+				.position(|x| *x != letters[index])
+				.map(|x| index + x)
+				.unwrap_or(0)
+		} else {
+			match run_start(index) {
+				0 => run_start(letters.len() - 1),
+				start => run_start(start - 1),
+			}
+		}
+	}
+
+	/// Writes the games back to the gamelist file they were loaded from. Only what changed is
+	/// rewritten; folders and whatever else the file has that [Game] doesn't know about are kept.
+	pub fn save_gamelist(&self) -> Result<()> {
+		let Some(filename) = &self.gamelist_path else {
+			tracing::warn!("system {} has no gamelist file to save to", self.name);
+			return Ok(());
+		};
+
+		GameList::save_games(filename, &self.games)
+	}
+
+	/// The labels of the commands to choose from, when the system has alternative emulators.
+	pub fn emulators(&self) -> Vec<String> {
+		if self.command.len() < 2 {
+			return Vec::new();
+		}
+
+		self.command
+			.iter()
+			.filter_map(|x| x.label.clone())
+			.collect()
+	}
+
+	/// The command launching the ROM at `path` with the emulator labelled `emulator`, or with the
+	/// default one.
+	pub fn get_command(&self, path: PathBuf, emulator: Option<&str>) -> String {
 		let rom = shell_escape::escape(path.to_string_lossy());
 		let basename = path.file_name().unwrap();
+		let command = emulator
+			.and_then(|label| {
+				self.command
+					.iter()
+					.find(|x| x.label.as_deref() == Some(label))
+			})
+			.or(self.command.first())
+			.map(|x| x.value.as_str())
+			.unwrap_or_default();
 
-		self.command
+		command
 			.replace("%ROM%", &rom)
-			.replace("%ROM_RAW", &path.to_string_lossy())
+			.replace("%ROM_RAW%", &path.to_string_lossy())
 			.replace("%BASENAME%", &basename.to_string_lossy())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::{Game, System, SystemCommand, SystemList};
+
+	#[test]
+	fn test_letter_jump() {
+		let mut system = System {
+			name: "test".into(),
+			fullname: "Test".into(),
+			path: "/".into(),
+			extension: ".zip".into(),
+			command: vec![SystemCommand {
+				label: None,
+				value: "true".into(),
+			}],
+			platform: "test".into(),
+			theme: None,
+			sort: Default::default(),
+			filter: Default::default(),
+			is_virtual: false,
+			gamelist: Vec::new(),
+			games: [
+				"'88 Games",
+				"1942",
+				"Asteroids",
+				"Avalon",
+				"Berzerk",
+				"Centipede",
+			]
+			.into_iter()
+			.map(|x| Game {
+				name: Some(x.into()),
+				..Default::default()
+			})
+			.collect(),
+			gamelist_path: None,
+		};
+		system.refresh();
+
+		assert_eq!(system.letter_jump(0, true), 2);
+		assert_eq!(system.letter_jump(2, true), 4);
+		assert_eq!(system.letter_jump(5, true), 0);
+		assert_eq!(system.letter_jump(3, false), 0);
+		assert_eq!(system.letter_jump(4, false), 2);
+		assert_eq!(system.letter_jump(0, false), 5);
+	}
+
+	#[test]
+	fn test_random_game() {
+		let mut systems = SystemList::from_file("test-systems.xml".into()).unwrap();
+		let game = |name: &str| Game {
+			name: Some(name.into()),
+			..Default::default()
+		};
+		systems.system[0].gamelist = vec![game("Tetris")];
+		systems.system[1].gamelist = vec![game("Columns"), game("Puyo Puyo")];
+
+		let mut rng = rand::rng();
+		for _ in 0..20 {
+			// never the current game while there is another
+			assert_eq!(systems.random_game(Some(1), (1, 0), &mut rng), Some((1, 1)));
+			assert_ne!(systems.random_game(None, (0, 0), &mut rng), Some((0, 0)));
+		}
+		assert_eq!(systems.random_game(Some(0), (0, 0), &mut rng), Some((0, 0)));
+		systems.system[0].gamelist.clear();
+		assert_eq!(systems.random_game(Some(0), (0, 0), &mut rng), None);
+	}
+
+	#[test]
+	fn test_alternative_emulators() {
+		let systems: SystemList = quick_xml::de::from_str(
+			r#"<systemList><system>
+				<name>snes</name>
+				<fullname>Super Nintendo</fullname>
+				<path>~/roms/snes</path>
+				<extension>.sfc</extension>
+				<command label="Snes9x">snes9x %ROM%</command>
+				<command label="bsnes">bsnes %ROM%</command>
+				<platform>snes</platform>
+			</system></systemList>"#,
+		)
+		.unwrap();
+
+		let system = &systems.system[0];
+		assert_eq!(system.emulators(), vec!["Snes9x", "bsnes"]);
+		assert_eq!(
+			system.get_command("/roms/a.sfc".into(), Some("bsnes")),
+			"bsnes /roms/a.sfc"
+		);
+		assert_eq!(
+			system.get_command("/roms/a.sfc".into(), Some("gone")),
+			"snes9x /roms/a.sfc"
+		);
+	}
+
+	#[test]
+	fn test_get_command() {
+		let systems: SystemList = quick_xml::de::from_str(
+			r#"<systemList><system>
+				<name>snes</name>
+				<fullname>Super Nintendo</fullname>
+				<path>~/roms/snes</path>
+				<extension>.sfc</extension>
+				<command>run %ROM% --raw=%ROM_RAW% --name=%BASENAME%</command>
+				<platform>snes</platform>
+			</system></systemList>"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			systems.system[0].get_command("/roms/Super Mario World.sfc".into(), None),
+			"run '/roms/Super Mario World.sfc' --raw=/roms/Super Mario World.sfc \
+			 --name=Super Mario World.sfc"
+		);
+	}
+}
@@ -0,0 +1,206 @@
+use crate::is_es_theme;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+/// The file that marks a directory under `themes/` as a theme.
+pub const THEME_MANIFEST: &str = "theme.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeFormat {
+	/// Styles and banners for the bundled frontend.
+	#[default]
+	Rawrcade,
+	/// An EmulationStation or ES-DE theme, drawn from its `theme.xml` layout.
+	EmulationStation,
+}
+
+/// Describes a theme. Paths are relative to the theme's directory. EmulationStation themes don't
+/// need one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeManifest {
+	pub name: String,
+	pub author: Option<String>,
+	pub version: Option<String>,
+	pub format: ThemeFormat,
+	/// Loaded by the frontend after its own styles.
+	pub stylesheet: Option<PathBuf>,
+	/// A directory of `<platform>.png` (or any image extension) system banners.
+	pub banner_dir: Option<PathBuf>,
+	/// Banners for individual platforms, taking precedence over `banner_dir`.
+	pub banners: BTreeMap<String, PathBuf>,
+	/// Any other named files the theme's styles refer to, like backgrounds or fonts.
+	pub assets: BTreeMap<String, PathBuf>,
+}
+
+impl ThemeManifest {
+	pub fn from_file(filename: &Path) -> Result<Self> {
+		let f = std::fs::OpenOptions::new().read(true).open(filename)?;
+
+		Ok(serde_json::from_reader(f)?)
+	}
+}
+
+/// An installed theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+	/// The name of the theme's directory, which is what the configuration refers to.
+	pub id: String,
+	pub path: PathBuf,
+	pub manifest: ThemeManifest,
+}
+
+impl Theme {
+	pub fn load(path: &Path) -> Result<Self> {
+		let manifest_file = path.join(THEME_MANIFEST);
+		let mut manifest = if !manifest_file.is_file() && is_es_theme(path) {
+			ThemeManifest {
+				format: ThemeFormat::EmulationStation,
+				..Default::default()
+			}
+		} else {
+			ThemeManifest::from_file(&manifest_file)?
+		};
+		let id = path
+			.file_name()
+			.map(|x| x.to_string_lossy().to_string())
+			.unwrap_or_default();
+
+		if manifest.name.is_empty() {
+			manifest.name = id.clone();
+		}
+
+		Ok(Self {
+			id,
+			path: path.to_path_buf(),
+			manifest,
+		})
+	}
+
+	/// Absolute paths to the banner of each platform the theme has one for.
+	pub fn banners(&self) -> BTreeMap<String, PathBuf> {
+		let mut banners = BTreeMap::new();
+
+		if let Some(dir) = &self.manifest.banner_dir {
+			for entry in std::fs::read_dir(self.path.join(dir))
+				.into_iter()
+				.flatten()
+				.flatten()
+			{
+				let path = entry.path();
+				if let Some(platform) = path.file_stem() {
+					if path.is_file() {
+						banners.insert(platform.to_string_lossy().to_string(), path);
+					}
+				}
+			}
+		}
+
+		for (platform, path) in &self.manifest.banners {
+			banners.insert(platform.clone(), self.path.join(path));
+		}
+
+		banners
+	}
+
+	/// What the frontend needs to draw with this theme.
+	pub fn active(&self) -> ActiveTheme {
+		ActiveTheme {
+			id: Some(self.id.clone()),
+			name: self.manifest.name.clone(),
+			format: self.manifest.format,
+			stylesheet: self.manifest.stylesheet.as_ref().map(|x| self.path.join(x)),
+			banners: self.banners(),
+			assets: self
+				.manifest
+				.assets
+				.iter()
+				.map(|(name, path)| (name.clone(), self.path.join(path)))
+				.collect(),
+		}
+	}
+}
+
+/// The theme in use, with every path made absolute. The built-in theme has no `id` and no
+/// files; the frontend falls back to its bundled banners for any platform missing here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveTheme {
+	pub id: Option<String>,
+	pub name: String,
+	pub format: ThemeFormat,
+	pub stylesheet: Option<PathBuf>,
+	pub banners: BTreeMap<String, PathBuf>,
+	pub assets: BTreeMap<String, PathBuf>,
+}
+
+impl Default for ActiveTheme {
+	fn default() -> Self {
+		Self {
+			id: None,
+			name: "Default".to_string(),
+			format: ThemeFormat::Rawrcade,
+			stylesheet: None,
+			banners: BTreeMap::new(),
+			assets: BTreeMap::new(),
+		}
+	}
+}
+
+/// Every theme under `dir`, sorted by id. Directories that are neither an EmulationStation theme
+/// nor have a readable manifest are skipped.
+pub fn discover_themes(dir: &Path) -> Vec<Theme> {
+	let mut themes: Vec<Theme> = std::fs::read_dir(dir)
+		.into_iter()
+		.flatten()
+		.flatten()
+		.map(|x| x.path())
+		.filter(|x| x.join(THEME_MANIFEST).is_file() || is_es_theme(x))
+		.filter_map(|x| match Theme::load(&x) {
+			Ok(theme) => Some(theme),
+			Err(e) => {
+				tracing::warn!("skipping theme {}: {}", x.display(), e);
+				None
+			}
+		})
+		.collect();
+
+	themes.sort_by(|a, b| a.id.cmp(&b.id));
+	themes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_discover_themes() {
+		let dir = std::env::temp_dir().join(format!("rawrcade-themes-{}", std::process::id()));
+		let theme = dir.join("neon");
+		std::fs::create_dir_all(theme.join("banners")).unwrap();
+		std::fs::create_dir_all(dir.join("not-a-theme")).unwrap();
+
+		std::fs::write(
+			theme.join(THEME_MANIFEST),
+			r#"{"name": "Neon", "banner_dir": "banners", "banners": {"mame": "arcade.png"}}"#,
+		)
+		.unwrap();
+		std::fs::write(theme.join("banners/nes.png"), "").unwrap();
+		std::fs::write(theme.join("banners/mame.png"), "").unwrap();
+
+		let themes = discover_themes(&dir);
+		assert_eq!(themes.len(), 1);
+		assert_eq!(themes[0].id, "neon");
+		assert_eq!(themes[0].manifest.name, "Neon");
+
+		let banners = themes[0].banners();
+		assert_eq!(banners["nes"], theme.join("banners/nes.png"));
+		assert_eq!(banners["mame"], theme.join("arcade.png"));
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,167 @@
+use crate::{Event, EventType};
+use anyhow::Result;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::{Duration, SystemTime},
+};
+use tokio::sync::mpsc::Sender;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watches `es_systems.cfg` and the gamelists under the data directory for changes made outside
+/// of the application, such as a scraper run. Modification times are polled, which also works
+/// when the gamelists are symlinked to another filesystem.
+#[derive(Debug, Clone, Default)]
+pub struct FileWatcher {
+	seen: Arc<Mutex<HashMap<PathBuf, SystemTime>>>,
+}
+
+impl FileWatcher {
+	pub async fn start(&self, root: PathBuf, sender: Sender<Event>) {
+		self.watch(root, sender, POLL_INTERVAL).await
+	}
+
+	async fn watch(&self, root: PathBuf, sender: Sender<Event>, every: Duration) {
+		// the first pass only records what is already there
+		self.poll_blocking(root.clone()).await;
+
+		let this = self.clone();
+		tauri::async_runtime::spawn(async move {
+			let mut interval = tokio::time::interval(every);
+			loop {
+				interval.tick().await;
+
+				let changed = this.poll_blocking(root.clone()).await;
+				if !changed.is_empty() {
+					let event = Event {
+						typ: EventType::FilesChanged(changed),
+					};
+
+					if sender.send(event).await.is_err() {
+						return;
+					}
+				}
+			}
+		});
+	}
+
+	/// Runs `write`, which writes `path`, so that it isn't reported as changed. No poll can happen
+	/// between the write and recording it.
+	pub fn write<T>(&self, path: &Path, write: impl FnOnce() -> Result<T>) -> Result<T> {
+		let mut seen = self.seen.lock().unwrap();
+		let result = write();
+		if let Some(modified) = modified(path) {
+			seen.insert(path.to_path_buf(), modified);
+		}
+
+		result
+	}
+
+	// the file system is read on the blocking pool, not the async runtime
+	async fn poll_blocking(&self, root: PathBuf) -> Vec<PathBuf> {
+		let this = self.clone();
+		tauri::async_runtime::spawn_blocking(move || this.poll(&root))
+			.await
+			.unwrap_or_default()
+	}
+
+	fn poll(&self, root: &Path) -> Vec<PathBuf> {
+		let mut seen = self.seen.lock().unwrap();
+		let mut changed = Vec::new();
+
+		for file in watched_files(root) {
+			let modified = modified(&file);
+			let previous = match modified {
+				Some(modified) => seen.insert(file.clone(), modified),
+				None => seen.remove(&file),
+			};
+
+			if previous != modified {
+				changed.push(file);
+			}
+		}
+
+		changed
+	}
+}
+
+fn watched_files(root: &Path) -> Vec<PathBuf> {
+	let mut files = vec![root.join("es_systems.cfg")];
+
+	if let Ok(dir) = std::fs::read_dir(root.join("gamelists")) {
+		files.extend(dir.flatten().map(|x| x.path().join("gamelist.xml")));
+	}
+
+	if let Ok(dir) = std::fs::read_dir(root.join("collections")) {
+		files.extend(
+			dir.flatten()
+				.map(|x| x.path())
+				.filter(|x| x.extension().is_some_and(|x| x == "cfg")),
+		);
+	}
+
+	files
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+	std::fs::metadata(path).and_then(|x| x.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::sync::mpsc::channel;
+
+	#[tokio::test]
+	async fn test_changes_and_acknowledged_writes() {
+		let root = std::env::temp_dir().join(format!("rawrcade-watch-{}", std::process::id()));
+		let systems = root.join("es_systems.cfg");
+		let gamelist = root.join("gamelists/snes/gamelist.xml");
+		std::fs::create_dir_all(gamelist.parent().unwrap()).unwrap();
+		std::fs::write(&systems, "<systemList />").unwrap();
+		std::fs::write(&gamelist, "<gameList />").unwrap();
+
+		let touch = |path: &Path, secs: u64| {
+			std::fs::File::options()
+				.write(true)
+				.open(path)
+				.unwrap()
+				.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+				.unwrap();
+		};
+
+		let (s, mut r) = channel(10);
+		let watcher = FileWatcher::default();
+		watcher
+			.watch(root.clone(), s, Duration::from_millis(10))
+			.await;
+		let mut next = async || {
+			let event = tokio::time::timeout(Duration::from_secs(5), r.recv())
+				.await
+				.unwrap()
+				.unwrap();
+			match event.typ {
+				EventType::FilesChanged(files) => files,
+				_ => panic!("not a file change"),
+			}
+		};
+
+		// written by a scraper
+		touch(&gamelist, 1_000_000);
+		assert_eq!(next().await, vec![gamelist.clone()]);
+
+		// written by the application itself, then something else changes: only that is reported
+		watcher
+			.write(&gamelist, || {
+				touch(&gamelist, 2_000_000);
+				Ok(())
+			})
+			.unwrap();
+		touch(&systems, 1_000_000);
+		assert_eq!(next().await, vec![systems.clone()]);
+
+		std::fs::remove_dir_all(root).unwrap();
+	}
+}
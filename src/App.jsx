@@ -1,40 +1,166 @@
-import { useState, useEffect } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { useState, useEffect, useRef } from "react";
+import { invoke, convertFileSrc } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import Snackbar from "@mui/material/Snackbar";
 import Theme from "./theme/Theme";
 
 function App() {
   const [systems, setSystems] = useState([]);
   const [orientation, setOrientation] = useState(null);
+  const [menu, setMenu] = useState(null);
+  const [settings, setSettings] = useState({});
+  const [controllers, setControllers] = useState([]);
+  const [theme, setTheme] = useState(null);
+  const [layout, setLayout] = useState(null);
+  const [setup, setSetup] = useState(null);
+  const [toast, setToast] = useState(null);
+  const [keys, setKeys] = useState([]);
+  const seqs = useRef({});
 
-  async function getSystems() {
-    setSystems(await invoke("all_systems"));
-  }
+  // the backend pushes state as it changes. each update carries a sequence number, so stale
+  // updates and late replies to the initial fetch can't overwrite newer state.
+  useEffect(() => {
+    const subscriptions = [
+      ["orientation", setOrientation],
+      ["menu", setMenu],
+      ["setup", setSetup],
+      ["systems", setSystems],
+      ["settings", setSettings],
+      ["controllers", setControllers],
+    ].map(([name, setter]) =>
+      listen(name, (e) => {
+        if (e.payload.seq > (seqs.current[name] || 0)) {
+          seqs.current[name] = e.payload.seq;
+          setter(e.payload.data);
+        }
+      })
+    );
+
+    const initial = [
+      ["all_systems", "systems", setSystems],
+      ["current_orientation", "orientation", setOrientation],
+      ["current_menu", "menu", setMenu],
+      ["setup_status", "setup", setSetup],
+      ["settings", "settings", setSettings],
+      ["controllers_menu", "controllers", setControllers],
+    ];
+
+    initial.forEach(async ([command, name, setter]) => {
+      const data = await invoke(command);
+      if (!seqs.current[name]) {
+        setter(data);
+      }
+    });
+
+    return () => {
+      subscriptions.forEach((unlisten) => unlisten.then((f) => f()));
+    };
+  }, []);
+
+  // the active theme only changes through the theme setting
+  useEffect(() => {
+    invoke("active_theme").then(setTheme);
+  }, [settings.theme]);
+
+  // EmulationStation themes are resolved per system, as they may have a folder for each
+  const systemName =
+    orientation && systems[orientation.system_index]
+      ? systems[orientation.system_index].name
+      : null;
+  useEffect(() => {
+    let current = true;
+    invoke("theme_layout", { system: systemName }).then((x) => {
+      if (current) {
+        setLayout(x);
+      }
+    });
+    return () => {
+      current = false;
+    };
+  }, [settings.theme, systemName]);
+
+  useEffect(() => {
+    const link = document.createElement("link");
+    link.rel = "stylesheet";
+
+    if (theme && theme.stylesheet) {
+      link.href = convertFileSrc(theme.stylesheet);
+      document.head.appendChild(link);
+    }
+
+    return () => link.remove();
+  }, [theme]);
 
-  async function getOrientation() {
-    setOrientation(await invoke("current_orientation"));
+  useEffect(() => {
     const element = document.getElementById("selected");
     if (element) {
       element.scrollIntoView();
     }
-  }
+  }, [orientation]);
 
   useEffect(() => {
-    const effect = async () => {
-      await getSystems();
+    const unlisten = listen("toast", (e) => setToast(e.payload));
+    return () => {
+      unlisten.then((f) => f());
     };
-    effect();
   }, []);
 
   useEffect(() => {
-    const effect = async () => {
-      await getOrientation();
-      const id = setInterval(async () => await getOrientation(), 50);
-      return () => clearInterval(id);
-    };
-    effect();
+    invoke("keyboard_keys").then(setKeys);
   }, []);
 
-  return <Theme systems={systems} orientation={orientation} />;
+  // the volume setting applies to every video and sound, including the ones themes add
+  useEffect(() => {
+    if (settings.volume === undefined) {
+      return;
+    }
+
+    const volume = settings.volume / 100;
+    const onLoadStart = (e) => {
+      if (e.target instanceof HTMLMediaElement) {
+        e.target.volume = volume;
+      }
+    };
+
+    document.querySelectorAll("video, audio").forEach((x) => (x.volume = volume));
+    document.addEventListener("loadstart", onLoadStart, true);
+    return () => document.removeEventListener("loadstart", onLoadStart, true);
+  }, [settings.volume]);
+
+  // only keys that do something are kept from the browser, and only while keyboard input is on
+  useEffect(() => {
+    const onKeyDown = async (e) => {
+      if (!settings.enable_keyboard || !keys.includes(e.key)) {
+        return;
+      }
+
+      e.preventDefault();
+      await invoke("keyboard_input", { key: e.key });
+    };
+    window.addEventListener("keydown", onKeyDown);
+    return () => window.removeEventListener("keydown", onKeyDown);
+  }, [settings.enable_keyboard, keys]);
+
+  return (
+    <>
+      <Theme
+        systems={systems}
+        orientation={orientation}
+        menu={menu}
+        settings={settings}
+        controllers={controllers}
+        theme={theme}
+        layout={layout}
+        setup={setup}
+      />
+      <Snackbar
+        open={toast !== null}
+        message={toast}
+        autoHideDuration={3000}
+        onClose={() => setToast(null)}
+      />
+    </>
+  );
 }
 
 export default App;
@@ -0,0 +1,248 @@
+import React from "react";
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+// how long the system view stays up after changing systems, like EmulationStation's transition
+const SYSTEM_VIEW_MS = 1500;
+
+// the selected game's media shown by the metadata images of the original EmulationStation, and
+// by the imageType property of ES-DE
+const GAME_MEDIA = {
+  md_image: "image",
+  md_thumbnail: "thumbnail",
+  md_marquee: "marquee",
+  md_video: "video",
+  screenshot: "image",
+  cover: "thumbnail",
+  marquee: "marquee",
+};
+
+// the selected game's fields shown by metadata texts, by element name or ES-DE's metadata property
+const GAME_TEXT = {
+  md_name: "name",
+  md_description: "desc",
+  md_developer: "developer",
+  md_publisher: "publisher",
+  md_genre: "genre",
+  md_players: "players",
+  md_playcount: "playcount",
+  md_rating: "rating",
+  md_releasedate: "releasedate",
+  md_lastplayed: "lastplayed",
+  name: "name",
+  description: "desc",
+  developer: "developer",
+  publisher: "publisher",
+  genre: "genre",
+  players: "players",
+  playcount: "playcount",
+  rating: "rating",
+  releasedate: "releasedate",
+  lastplayed: "lastplayed",
+};
+
+// positions, sizes and font sizes are fractions of the screen
+function elementStyle(element) {
+  const p = element.properties;
+  const [x, y] = element.pos;
+  const [ox, oy] = element.origin || [0, 0];
+  const size = element.size || [0, 0];
+
+  return {
+    position: "absolute",
+    left: `${x * 100}%`,
+    top: `${y * 100}%`,
+    width: size[0] > 0 ? `${size[0] * 100}%` : undefined,
+    height: size[1] > 0 ? `${size[1] * 100}%` : undefined,
+    transform: `translate(${-ox * 100}%, ${-oy * 100}%)`,
+    zIndex: Math.round(element.z_index),
+    color: p.color,
+    backgroundColor: p.backgroundColor,
+    fontSize: p.fontSize ? `${p.fontSize * 100}vh` : undefined,
+    textAlign: p.alignment || p.horizontalAlignment,
+    textTransform: p.forceUppercase == "true" ? "uppercase" : undefined,
+  };
+}
+
+// gamelist dates are YYYYMMDDTHHMMSS
+function formatDate(value) {
+  if (!value || value.length < 8) {
+    return value || "";
+  }
+
+  return `${value.substr(0, 4)}-${value.substr(4, 2)}-${value.substr(6, 2)}`;
+}
+
+function gameMedia(element, game) {
+  const media =
+    GAME_MEDIA[element.name] || GAME_MEDIA[element.properties.imageType];
+  const path = media ? game && game[media] : element.properties.path;
+
+  return path || element.properties.default;
+}
+
+function gameText(element, game) {
+  const field =
+    GAME_TEXT[element.name] || GAME_TEXT[element.properties.metadata];
+
+  if (!field) {
+    return element.properties.text || "";
+  }
+
+  const value = game ? game[field] : null;
+  return value === null || value === undefined ? "" : String(value);
+}
+
+function GameTextList(props) {
+  const element = props.element;
+  const p = element.properties;
+
+  return (
+    <div className="es-textlist" style={elementStyle(element)}>
+      {(props.games || []).map((x, i) =>
+        props.current == i ? (
+          <div
+            key={i}
+            id="selected"
+            style={{
+              color: p.selectedColor || p.primaryColor,
+              backgroundColor: p.selectorColor,
+            }}
+          >
+            {x.favorite ? "★ " : ""}
+            {x.name}
+          </div>
+        ) : (
+          <div key={i} style={{ color: p.primaryColor }}>
+            {x.favorite ? "★ " : ""}
+            {x.name}
+          </div>
+        )
+      )}
+    </div>
+  );
+}
+
+function EsElement(props) {
+  const element = props.element;
+  const game = props.game;
+  const style = elementStyle(element);
+
+  switch (element.kind) {
+    case "image": {
+      const path = gameMedia(element, game);
+      return path ? (
+        <img className="es-image" style={style} src={convertFileSrc(path)} />
+      ) : (
+        <React.Fragment />
+      );
+    }
+    case "video": {
+      const path = gameMedia(element, game);
+      return path ? (
+        <video
+          key={path}
+          className="es-image"
+          style={style}
+          src={convertFileSrc(path)}
+          autoPlay
+          loop
+        />
+      ) : (
+        <React.Fragment />
+      );
+    }
+    case "text":
+      return <div style={style}>{gameText(element, game)}</div>;
+    case "datetime":
+      return <div style={style}>{formatDate(gameText(element, game))}</div>;
+    case "rating": {
+      const stars = Math.round((parseFloat(game && game.rating) || 0) * 5);
+      return (
+        <div style={style}>{"★".repeat(stars) + "☆".repeat(5 - stars)}</div>
+      );
+    }
+    case "textlist":
+      return (
+        <GameTextList
+          element={element}
+          games={props.games}
+          current={props.current}
+        />
+      );
+    case "carousel":
+      // the carousel shows the logo the theme gives the system, or its name
+      return (
+        <div className="es-carousel" style={style}>
+          {props.logo ? (
+            <img className="es-image" src={convertFileSrc(props.logo)} />
+          ) : (
+            props.system.fullname
+          )}
+        </div>
+      );
+    default:
+      return <React.Fragment />;
+  }
+}
+
+// draws one view of an EmulationStation theme. elements the theme doesn't place are left out, as
+// the positions EmulationStation gives them aren't known here.
+function EsView(props) {
+  const view = props.view;
+  const logo = view.elements.find((x) => x.name == "logo" && !x.extra);
+
+  return (
+    <div className={props.className}>
+      {view.elements
+        .filter((x) => x.visible && x.pos && x !== logo)
+        .map((x, i) => (
+          <EsElement
+            key={`${x.kind}-${x.name}-${i}`}
+            element={x}
+            system={props.system}
+            games={props.system.gamelist}
+            current={props.current}
+            game={props.system.gamelist && props.system.gamelist[props.current]}
+            logo={logo && logo.properties.path}
+          />
+        ))}
+    </div>
+  );
+}
+
+// the game list as laid out by an EmulationStation theme, with its system view shown briefly
+// whenever the system changes
+function EsLayout(props) {
+  const layout = props.layout;
+  const system = props.system;
+  const [showSystem, setShowSystem] = React.useState(true);
+
+  React.useEffect(() => {
+    setShowSystem(true);
+    const timeout = setTimeout(() => setShowSystem(false), SYSTEM_VIEW_MS);
+    return () => clearTimeout(timeout);
+  }, [system.name]);
+
+  return (
+    <React.Fragment>
+      <EsView
+        className="es-view"
+        view={layout.views.gamelist}
+        system={system}
+        current={props.current}
+      />
+      {showSystem && layout.views.system ? (
+        <EsView
+          className="es-view es-system-view"
+          view={layout.views.system}
+          system={system}
+          current={props.current}
+        />
+      ) : (
+        <React.Fragment />
+      )}
+    </React.Fragment>
+  );
+}
+
+export default EsLayout;
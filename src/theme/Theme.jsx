@@ -4,56 +4,115 @@ import Container from "@mui/system/Container";
 import Stack from "@mui/system/Stack";
 import Grid from "@mui/system/Grid";
 import Popover from "@mui/material/Popover";
+import Slider from "@mui/material/Slider";
 import Switch from "@mui/material/Switch";
+import EsLayout from "./EsLayout";
 import "./Theme.css";
 import { invoke, convertFileSrc } from "@tauri-apps/api/core";
 
-let CURRENT_MENU_VALUES = [];
-let CURRENT_MENU_TYPES = [];
-let CURRENT_MENU = [];
-let CURRENT_MENU_INDEX = [];
 let CURRENT_GAMELIST_ASSETS = null;
 
-function pickSelector(i, item) {
-  if (CURRENT_MENU_TYPES[i] && CURRENT_MENU_VALUES[i]) {
-    switch (CURRENT_MENU_TYPES[i]) {
-      case "boolean":
-        return (
-          <React.Fragment>
-            <div>{item}</div>
-            <Switch
-              checked={JSON.parse(CURRENT_MENU_VALUES[i])}
-              sx={{
-                display: "flex",
-                marginLeft: "auto",
-                justifyContent: "flex-end",
-              }}
-            >
-              {CURRENT_MENU_VALUES[i]}
-            </Switch>
-          </React.Fragment>
-        );
-      case "string":
-        return (
-          <React.Fragment>
-            <div>{item}</div>
-            <div
-              style={{
-                display: "flex",
-                marginLeft: "auto",
-                justifyContent: "flex-end",
-              }}
-            >
-              {JSON.parse(CURRENT_MENU_VALUES[i]) || "<None>"}
-            </div>
-          </React.Fragment>
-        );
-    }
-  } else {
-    return item;
+const VALUE_STYLE = {
+  display: "flex",
+  marginLeft: "auto",
+  justifyContent: "flex-end",
+};
+
+function pickSelector(entry) {
+  switch (entry.type) {
+    case "toggle":
+      return (
+        <React.Fragment>
+          <div>{entry.label}</div>
+          <Switch checked={entry.value} sx={VALUE_STYLE} />
+        </React.Fragment>
+      );
+    case "choice":
+      return (
+        <React.Fragment>
+          <div>{entry.label}</div>
+          <div style={VALUE_STYLE}>
+            {"◄ "}
+            {entry.options[entry.selected]}
+            {" ►"}
+          </div>
+        </React.Fragment>
+      );
+    case "range":
+      return (
+        <React.Fragment>
+          <div>{entry.label}</div>
+          <Slider
+            value={entry.value}
+            min={entry.min}
+            max={entry.max}
+            step={entry.step}
+            sx={{ ...VALUE_STYLE, width: "40%" }}
+          />
+          <div className="menu-range-value">{entry.value}</div>
+        </React.Fragment>
+      );
+    case "list":
+      return (
+        <React.Fragment>
+          <div>{entry.label}</div>
+          <div style={VALUE_STYLE}>
+            {(entry.options.find((x) => x.value == entry.value) || entry).label}
+            {" ►"}
+          </div>
+        </React.Fragment>
+      );
+    case "select":
+      return (
+        <React.Fragment>
+          <div>{entry.label}</div>
+          <div style={VALUE_STYLE}>{entry.selected ? "✓" : ""}</div>
+        </React.Fragment>
+      );
+    case "text":
+      return (
+        <React.Fragment>
+          <div>{entry.label}</div>
+          <div style={VALUE_STYLE}>{entry.value}</div>
+        </React.Fragment>
+      );
+    case "submenu":
+      return (
+        <React.Fragment>
+          <div>{entry.label}</div>
+          <div style={VALUE_STYLE}>►</div>
+        </React.Fragment>
+      );
+    default:
+      return entry.label;
   }
 }
 
+function menuItemClass(i, selected) {
+  if (i == selected) {
+    return "menu-item menu-selected";
+  } else if (i == selected - 1 && i == 0) {
+    return "menu-item menu-not-selected-previous-first-item";
+  } else if (i == selected - 1) {
+    return "menu-item menu-not-selected-previous-item";
+  } else if (i == selected + 1) {
+    return "menu-item menu-not-selected-next-item";
+  } else if (i == 0) {
+    return "menu-item menu-not-selected-first-item";
+  }
+
+  return "menu-item menu-not-selected";
+}
+
+// themes may leave out platforms, which then use the bundled banners
+function systemBanner(theme, platform) {
+  if (theme && theme.banners[platform]) {
+    return convertFileSrc(theme.banners[platform]);
+  }
+
+  return `theme/${platform}.png`;
+}
+
 async function getAsset(t) {
   return await invoke("current_asset", { assetType: t });
 }
@@ -68,6 +127,269 @@ async function getText(t) {
   return res;
 }
 
+const KEY_LABELS = {
+  space: "Space",
+  shift: "⇧",
+  backspace: "⌫",
+  clear: "Clear",
+  done: "Done",
+};
+
+const KEYBOARD_TITLES = {
+  search: "Search",
+  new_collection: "New Collection",
+  edit_metadata: "Edit",
+};
+
+function SearchKeyboard(props) {
+  const keyboard = props.keyboard;
+
+  return (
+    <div className="menu-root search-keyboard">
+      <div className="menu-item dialog-title">
+        {KEYBOARD_TITLES[keyboard.purpose]}
+      </div>
+      <div className="menu-item search-text">
+        {keyboard.text}
+        <span className="search-cursor">_</span>
+      </div>
+      {keyboard.rows.map((row, r) => (
+        <div key={r} className="search-row">
+          {row.map((key, c) => (
+            <div
+              key={c}
+              className={
+                keyboard.row == r && keyboard.column == c
+                  ? "search-key menu-selected"
+                  : "search-key menu-not-selected"
+              }
+            >
+              {key.type == "char"
+                ? keyboard.shift
+                  ? key.value.toUpperCase()
+                  : key.value.toLowerCase()
+                : KEY_LABELS[key.type]}
+            </div>
+          ))}
+        </div>
+      ))}
+    </div>
+  );
+}
+
+function Screensaver(props) {
+  const slide = props.screensaver.slide;
+
+  if (!slide) {
+    return <div className="screensaver screensaver-dim" />;
+  }
+
+  return (
+    <div className="screensaver">
+      {slide.video ? (
+        <video
+          key={slide.video}
+          className="screensaver-media"
+          src={convertFileSrc(slide.video)}
+          autoPlay
+          loop
+        />
+      ) : slide.image ? (
+        <img
+          key={slide.image}
+          className="screensaver-media"
+          src={convertFileSrc(slide.image)}
+        />
+      ) : (
+        <React.Fragment />
+      )}
+      <div className="screensaver-caption">
+        <div className="screensaver-system">{slide.system}</div>
+        <div>{slide.name}</div>
+      </div>
+    </div>
+  );
+}
+
+const MEDIA_LABELS = {
+  image: "Screenshot",
+  thumbnail: "Box Art",
+  marquee: "Marquee",
+  video: "Video",
+};
+
+function GameDetails(props) {
+  const orientation = props.orientation;
+  const [details, setDetails] = React.useState(null);
+  const description = React.useRef(null);
+
+  React.useEffect(() => {
+    invoke("current_details").then(setDetails);
+  }, [orientation]);
+
+  React.useEffect(() => {
+    // each step scrolls by two lines
+    if (description.current && details) {
+      const line =
+        parseFloat(getComputedStyle(description.current).lineHeight) || 24;
+      description.current.scrollTop = details.scroll * 2 * line;
+    }
+  }, [details]);
+
+  if (!details) {
+    return <React.Fragment />;
+  }
+
+  return (
+    <div className="menu-root game-details">
+      <div className="menu-item dialog-title">{details.name}</div>
+      <div className="details-system">{details.system}</div>
+      <div className="details-body">
+        <div className="details-media">
+          {details.media.map((x) => (
+            <figure key={x.path}>
+              {x.kind == "video" ? (
+                <video src={convertFileSrc(x.path)} autoPlay loop />
+              ) : (
+                <img
+                  src={convertFileSrc(x.path)}
+                  onError={(e) => (e.target.style.display = "none")}
+                />
+              )}
+              <figcaption>{MEDIA_LABELS[x.kind]}</figcaption>
+            </figure>
+          ))}
+        </div>
+        <div className="details-info">
+          <table className="details-fields">
+            <tbody>
+              {details.fields.map((x) => (
+                <tr key={x.label}>
+                  <th>{x.label}</th>
+                  <td>{x.value}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+          {details.description ? (
+            <div className="details-description" ref={description}>
+              {details.description}
+            </div>
+          ) : (
+            <React.Fragment />
+          )}
+        </div>
+      </div>
+      {details.actions.map((x, i) => (
+        <div
+          key={x.action}
+          className={
+            details.selected == i
+              ? "menu-item menu-selected"
+              : "menu-item menu-not-selected"
+          }
+        >
+          {x.label}
+        </div>
+      ))}
+    </div>
+  );
+}
+
+function MetadataEditor(props) {
+  const orientation = props.orientation;
+  const [editor, setEditor] = React.useState(null);
+
+  React.useEffect(() => {
+    invoke("current_editor").then(setEditor);
+  }, [orientation]);
+
+  if (!editor) {
+    return <React.Fragment />;
+  }
+
+  return (
+    <div className="menu-root">
+      <div className="menu-item dialog-title">Edit {editor.name}</div>
+      {editor.entries.map((x, i) => (
+        <div
+          key={x.field}
+          className={
+            editor.selected == i
+              ? "menu-item menu-selected"
+              : "menu-item menu-not-selected"
+          }
+        >
+          {x.label}: {x.value}
+        </div>
+      ))}
+    </div>
+  );
+}
+
+function MenuLevel(props) {
+  const menu = props.menu;
+
+  return (
+    <div className="menu-root">
+      {menu.depth > 1 ? (
+        <div className="menu-item dialog-title">{menu.title}</div>
+      ) : (
+        <React.Fragment />
+      )}
+      {menu.entries.map((entry, i) => (
+        <div key={i} className={menuItemClass(i, menu.selected)}>
+          {pickSelector(entry)}
+        </div>
+      ))}
+    </div>
+  );
+}
+
+// explains what is missing on first run, instead of an empty game list
+function Onboarding(props) {
+  const setup = props.setup;
+
+  switch (setup.type) {
+    case "missing_data_directory":
+      return (
+        <div className="onboarding">
+          <h1>Welcome to RAWRcade</h1>
+          <p>There is no game library at {setup.path} yet.</p>
+          <p>Press OK to create it with a starter es_systems.cfg.</p>
+        </div>
+      );
+    case "missing_systems":
+      return (
+        <div className="onboarding">
+          <h1>No Systems Configured</h1>
+          <p>{setup.path} is missing.</p>
+          <p>Press OK to create a starter one.</p>
+        </div>
+      );
+    case "no_games":
+      return (
+        <div className="onboarding">
+          <h1>No Games Found</h1>
+          <p>Add your systems to {setup.systems}.</p>
+          <p>
+            Then put each system's gamelist.xml in {setup.gamelists}
+            /&lt;system name&gt;/. The library reloads on its own.
+          </p>
+        </div>
+      );
+    case "broken":
+      return (
+        <div className="onboarding">
+          <h1>Could Not Load Your Library</h1>
+          <p>{setup.error}</p>
+        </div>
+      );
+    default:
+      return <React.Fragment />;
+  }
+}
+
 function NoGameList() {
   return <div>No Game List Provided</div>;
 }
@@ -97,13 +419,15 @@ async function populateGameListAssets(current) {
 function GameList(props) {
   const list = props.list;
   const current = props.current;
+  const onLoaded = props.onLoaded;
 
   React.useEffect(() => {
     const effect = async () => {
       await populateGameListAssets(current || 0);
+      onLoaded();
     };
     effect();
-  }, [current]);
+  }, [current, list]);
 
   if (!list || list.length == 0) {
     return <NoGameList />;
@@ -116,10 +440,12 @@ function GameList(props) {
           <div key={i} id="selected" class="game">
             <span class="arrow">►</span>
             {"  "}
+            {x.favorite ? "★ " : ""}
             {x.name}
           </div>
         ) : (
           <div key={i} class="game not-selected">
+            {x.favorite ? "★ " : ""}
             {x.name}
           </div>
         );
@@ -135,183 +461,183 @@ function Theme(props) {
     orientation && systems && systems.length > 0
       ? systems[orientation.system_index]
       : {};
+  const [, forceUpdate] = React.useReducer((x) => x + 1, 0);
 
-  React.useEffect(() => {
-    const effect = async () => {
-      if (orientation && orientation.menu_item_index === null) {
-        console.log("fetching menu");
-        const menu = await invoke("menu");
-        CURRENT_MENU = menu;
-        CURRENT_MENU_VALUES = [];
-        CURRENT_MENU_TYPES = [];
-      }
-    };
-    effect();
-  }, [
-    orientation && orientation.menu_item_index === null
-      ? orientation.menu_active
-      : null,
-  ]);
-
-  React.useEffect(() => {
-    if (orientation && orientation.menu_item_index === null) {
-      console.log("setting menu index");
-      CURRENT_MENU_INDEX = orientation.menu_index;
-    }
-  }, [
-    orientation &&
-    orientation.menu_active &&
-    orientation.menu_item_index === null
-      ? orientation.menu_index
-      : null,
-  ]);
-
-  React.useEffect(() => {
-    if (
-      orientation &&
-      orientation.menu_active &&
-      orientation.menu_item_index !== null
-    ) {
-      console.log("setting menu item index");
-      CURRENT_MENU_INDEX = orientation.menu_item_index;
-    }
-  }, [
-    orientation &&
-    orientation.menu_active &&
-    orientation.menu_item_index !== null
-      ? orientation.menu_item_index
-      : null,
-  ]);
-
-  React.useEffect(() => {
-    const effect = async () => {
-      console.log("submenu trigger");
-      let interval = null;
-
-      if (orientation && orientation.menu_item_index !== null) {
-        console.log("finding submenu");
-        switch (orientation.menu_index) {
-          case 0: {
-            console.log("fetching settings submenu & types");
-            const menu = await invoke("settings_menu");
-            CURRENT_MENU = menu;
-
-            const types = await invoke("setting_types");
-            CURRENT_MENU_TYPES = types;
-            CURRENT_MENU_VALUES = await invoke("setting_values");
-
-            interval = setInterval(async () => {
-              CURRENT_MENU_VALUES = await invoke("setting_values");
-            }, 200);
-
-            console.log(CURRENT_MENU_VALUES);
-          }
-        }
-      }
+  const menu = props.menu;
 
-      return () => {
-        interval && clearInterval(interval);
-      };
-    };
-    effect();
-  }, [
-    orientation &&
-      orientation.menu_active &&
-      orientation.menu_item_index !== null,
-  ]);
+  if (props.setup && props.setup.type !== "ready") {
+    return (
+      <React.Fragment>
+        <Onboarding setup={props.setup} />
+        <Popover className="menu-popover" open={Boolean(menu)}>
+          {menu ? <MenuLevel menu={menu} /> : <React.Fragment />}
+        </Popover>
+      </React.Fragment>
+    );
+  }
 
   return (
     <React.Fragment>
-      <Container maxWidth="100%">
-        <Stack spacing={2}>
-          <div className="section system-info">
-            <Grid container spacing={2}>
-              <Grid size={4}>
-                <div>
-                  <Box className="system-banner">
+      {props.layout && props.layout.views.gamelist && current_system ? (
+        <EsLayout
+          layout={props.layout}
+          system={current_system}
+          current={orientation ? orientation.gamelist_index : 0}
+        />
+      ) : (
+        <Container maxWidth="100%">
+          <Stack spacing={2}>
+            <div className="section system-info">
+              <Grid container spacing={2}>
+                <Grid size={4}>
+                  <div>
+                    <Box className="system-banner">
+                      {current_system ? (
+                        <img
+                          key={current_system.platform}
+                          class="system-banner"
+                          src={systemBanner(props.theme, current_system.platform)}
+                          onError={(e) => (e.target.style.visibility = "hidden")}
+                        />
+                      ) : (
+                        <React.Fragment />
+                      )}
+                    </Box>
+                  </div>
+                </Grid>
+                <Grid size={8}>
+                  <div className="system-title">
+                    {orientation && systems.length > 0
+                      ? systems[orientation.system_index].fullname
+                      : "No Systems Loaded"}
+                  </div>
+                </Grid>
+              </Grid>
+            </div>
+            <div className="section">
+              <Grid container spacing={2}>
+                <Grid size={6} className="game-list">
+                  <div>
                     {current_system ? (
-                      <img
-                        class="system-banner"
-                        src={`theme/${current_system.platform}.png`}
+                      <GameList
+                        list={current_system.gamelist}
+                        current={orientation ? orientation.gamelist_index : 0}
+                        onLoaded={forceUpdate}
                       />
                     ) : (
-                      <React.Fragment />
+                      <NoGameList />
                     )}
-                  </Box>
-                </div>
-              </Grid>
-              <Grid size={8}>
-                <div className="system-title">
-                  {orientation && systems.length > 0
-                    ? systems[orientation.system_index].fullname
-                    : "No Systems Loaded"}
-                </div>
-              </Grid>
-            </Grid>
-          </div>
-          <div className="section">
-            <Grid container spacing={2}>
-              <Grid size={6} className="game-list">
-                <div>
-                  {current_system ? (
-                    <GameList
-                      list={current_system.gamelist}
-                      current={orientation ? orientation.gamelist_index : 0}
-                    />
+                  </div>
+                </Grid>
+                <Grid size={6} className="current-game">
+                  {CURRENT_GAMELIST_ASSETS ? (
+                    CURRENT_GAMELIST_ASSETS.image
                   ) : (
-                    <NoGameList />
+                    <div />
                   )}
-                </div>
-              </Grid>
-              <Grid size={6} className="current-game">
-                {CURRENT_GAMELIST_ASSETS ? (
-                  CURRENT_GAMELIST_ASSETS.image
-                ) : (
-                  <div />
-                )}
-                {CURRENT_GAMELIST_ASSETS ? (
-                  CURRENT_GAMELIST_ASSETS.description
-                ) : (
-                  <div />
-                )}
+                  {CURRENT_GAMELIST_ASSETS ? (
+                    CURRENT_GAMELIST_ASSETS.description
+                  ) : (
+                    <div />
+                  )}
+                </Grid>
               </Grid>
-            </Grid>
+            </div>
+          </Stack>
+        </Container>
+      )}
+      <Popover className="menu-popover" open={Boolean(menu)}>
+        {menu ? <MenuLevel menu={menu} /> : <React.Fragment />}
+      </Popover>
+      <Popover
+        className="menu-popover"
+        open={Boolean(orientation && orientation.keyboard)}
+      >
+        {orientation && orientation.keyboard ? (
+          <SearchKeyboard keyboard={orientation.keyboard} />
+        ) : (
+          <React.Fragment />
+        )}
+      </Popover>
+      <Popover
+        className="menu-popover"
+        open={Boolean(orientation && orientation.unlock)}
+      >
+        {orientation && orientation.unlock ? (
+          <div className="menu-root">
+            <div className="menu-item dialog-title">Enter PIN</div>
+            <div className="menu-item">
+              {"●".repeat(orientation.unlock.length)}
+              <span className="search-cursor">_</span>
+            </div>
           </div>
-        </Stack>
-      </Container>
+        ) : (
+          <React.Fragment />
+        )}
+      </Popover>
+      {orientation && orientation.letter ? (
+        <div className="letter-overlay">{orientation.letter}</div>
+      ) : (
+        <React.Fragment />
+      )}
       <Popover
         className="menu-popover"
-        open={CURRENT_MENU.length > 0 && orientation && orientation.menu_active}
+        open={Boolean(
+          orientation &&
+            orientation.views.length > 0 &&
+            orientation.views[orientation.views.length - 1].type == "details",
+        )}
       >
-        <div className="menu-root">
-          {CURRENT_MENU.map((item, i) =>
-            CURRENT_MENU_INDEX == i ? (
-              <div className="menu-item menu-selected">
-                {pickSelector(i, item)}
-              </div>
-            ) : i == CURRENT_MENU_INDEX - 1 && i == 0 ? (
-              <div className="menu-item menu-not-selected-previous-first-item">
-                {pickSelector(i, item)}
-              </div>
-            ) : i == CURRENT_MENU_INDEX - 1 ? (
-              <div className="menu-item menu-not-selected-previous-item">
-                {pickSelector(i, item)}
-              </div>
-            ) : i == CURRENT_MENU_INDEX + 1 ? (
-              <div className="menu-item menu-not-selected-next-item">
-                {pickSelector(i, item)}
-              </div>
-            ) : i == 0 ? (
-              <div className="menu-item menu-not-selected-first-item">
-                {pickSelector(i, item)}
-              </div>
-            ) : (
-              <div className="menu-item menu-not-selected">
-                {pickSelector(i, item)}
-              </div>
-            )
-          )}
+        <GameDetails orientation={orientation} />
+      </Popover>
+      <Popover
+        className="menu-popover"
+        open={Boolean(
+          orientation &&
+            !orientation.keyboard &&
+            orientation.views.length > 0 &&
+            orientation.views[orientation.views.length - 1].type == "editor",
+        )}
+      >
+        <MetadataEditor orientation={orientation} />
+      </Popover>
+      {orientation && orientation.screensaver ? (
+        <Screensaver screensaver={orientation.screensaver} />
+      ) : (
+        <React.Fragment />
+      )}
+      {orientation && orientation.countdown ? (
+        <div className="countdown-overlay">
+          <div>{orientation.countdown}</div>
+          <div className="countdown-hint">Press any button to cancel</div>
         </div>
+      ) : (
+        <React.Fragment />
+      )}
+      <Popover
+        className="menu-popover"
+        open={Boolean(orientation && orientation.dialog)}
+      >
+        {orientation && orientation.dialog ? (
+          <div className="menu-root">
+            <div className="menu-item dialog-title">
+              {orientation.dialog.title}
+            </div>
+            {orientation.dialog.options.map((option, i) =>
+              orientation.dialog.selected == i ? (
+                <div key={i} className="menu-item menu-selected">
+                  {option}
+                </div>
+              ) : (
+                <div key={i} className="menu-item menu-not-selected">
+                  {option}
+                </div>
+              )
+            )}
+          </div>
+        ) : (
+          <React.Fragment />
+        )}
       </Popover>
     </React.Fragment>
   );